@@ -1,6 +1,6 @@
 use std::error::Error;
 
-use robot_rs::{actuators::motors::MotorController, control::{control_lock::{ControlLockResource, ControlLock}, pid::{PID, PIDConfig}}, sensors::distance::{DistanceSource, SimDistanceSource}, types::MinMax, models::DcMotor, time::now};
+use robot_rs::{actuators::motors::MotorController, control::{control_lock::{ControlLockResource, ControlLock}, feedforward::ElevatorFeedforward, pid::PIDConfig, profiled_pid::ProfiledPID, profile::{TrapezoidProfile, TrapezoidConstraints, ProfileState}}, sensors::distance::{DistanceSource, SimDistanceSource}, types::MinMax, models::DcMotor, time::now};
 use strum::Display;
 use tokio::sync::RwLock;
 use nt4_rs::nt;
@@ -52,7 +52,8 @@ pub struct Elevator<'a, M, H> {
   config: ElevatorConfig<M, H>,
   state: RwLock<ElevatorState>,
   control_lock: ControlLockResource<'a, Self>,
-  pid: RwLock<PID>,
+  pid: RwLock<ProfiledPID<TrapezoidProfile>>,
+  feedforward: ElevatorFeedforward,
 
   sim_speed: RwLock<f64>
 }
@@ -94,12 +95,13 @@ where
   M: MotorController,
   H: DistanceSource
 {
-  pub fn new(config: ElevatorConfig<M, H>, pid_config: PIDConfig) -> Self {
+  pub fn new(config: ElevatorConfig<M, H>, pid_config: PIDConfig, constraints: TrapezoidConstraints) -> Self {
     Self {
+      feedforward: ElevatorFeedforward::from_motor(&config.motor_model, config.mass, config.spool_radius),
       config,
       state: RwLock::new(ElevatorState::Idle),
       control_lock: Default::default(),
-      pid: RwLock::new(PID::new(pid_config, 0.0, 0.2)),
+      pid: RwLock::new(ProfiledPID::new(pid_config, constraints, 0.2)),
 
       sim_speed: RwLock::new(0.0)
     }
@@ -108,7 +110,8 @@ where
   /// Run the elevator. This is an async function, so you can call it and let it loop
   /// using something like tokio::task::spawn(), instead of needing to spin up a new thread.
   /// This function will also update NetworkTables with information about the elevator and its PID loop.
-  /// PID loop coefficients can also be updated from NetworkTables.
+  /// PID loop coefficients can also be updated from NetworkTables. The height setpoint follows a trapezoidal
+  /// motion profile, so the elevator accelerates smoothly instead of slamming towards its goal.
   pub async fn run(&self) {
     loop {
       let mut demand_voltage = 0.0;
@@ -122,14 +125,12 @@ where
           demand_voltage = *voltage
         },
         ElevatorState::HeightControl { height } => {
-          pid.set_setpoint(*height);
+          pid.set_goal(ProfileState::at_rest(*height));
           
-          let feedforward = self.config.motor_model.voltage(
-            self.config.mass * 9.81 * self.config.spool_radius,
-            0.0
-          );
+          let output = pid.calculate(current_height, now());
+          let feedforward = self.feedforward.calculate(output.setpoint.velocity, output.setpoint.acceleration);
 
-          demand_voltage = pid.calculate(current_height, now()).output + feedforward;
+          demand_voltage = output.output + feedforward;
         }
       }
 
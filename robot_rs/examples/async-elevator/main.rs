@@ -3,10 +3,10 @@ pub mod elevator;
 use std::sync::Arc;
 
 use elevator::{Elevator, AbstractElevator, ElevatorResult};
-use futures::{future::join_all, FutureExt};
+use futures::{stream::FuturesUnordered, FutureExt, StreamExt};
 use log::info;
 use nt4_rs::instance::NetworkTableInstance;
-use robot_rs::{start::RobotResult, robot_main, actuators::motors::{PWMSparkMax, ClampedMotor}, sensors::{analog::AnalogInput, distance::NaiveDistanceSource}, input::xbox::Xbox, control::{edge_detect::Edge, pid::PIDConfig}, types::MinMax, models::DcMotor, robot_init};
+use robot_rs::{start::RobotResult, robot_main, actuators::motors::{PWMSparkMax, ClampedMotor}, sensors::{analog::AnalogInput, distance::NaiveDistanceSource}, input::{xbox::Xbox, events::HIDEvent}, control::{pid::PIDConfig, profile::TrapezoidConstraints}, types::MinMax, models::DcMotor, robot_init};
 use tokio::sync::RwLock;
 
 use crate::elevator::ElevatorConfig;
@@ -58,12 +58,21 @@ async fn auto_routine<'a, E: AbstractElevator<'a>>(elevator: &'a E) -> ElevatorR
 
 /// Simple function to handle button presses scheduling new activities
 async fn buttons<'a, E: AbstractElevator<'a> + Send + Sync>(elevator: &'a E, xbox: &Xbox) {
-  let mut futs = vec![];
-  futs.push(xbox.a().edge_take(Edge::Falling).run(|| { move_to_height(elevator, 1.0) }).boxed());
-  futs.push(xbox.b().edge_take(Edge::Falling).run(|| { manual_controls(elevator, xbox) }).boxed());
-
-  // Join all the futures, running them concurrently within this function until either of them complete (never)
-  join_all(futs).await;
+  let (a, b) = (xbox.a().index(), xbox.b().index());
+  let mut events = Box::pin(xbox.events());
+  let mut running = FuturesUnordered::new();
+
+  // Handle events as they come in, while running the activities they've started concurrently within this function
+  loop {
+    tokio::select! {
+      Some(event) = events.next() => match event {
+        HIDEvent::Released { button } if button == a => running.push(move_to_height(elevator, 1.0).boxed()),
+        HIDEvent::Released { button } if button == b => running.push(manual_controls(elevator, xbox).boxed()),
+        _ => ()
+      },
+      Some(_) = running.next(), if !running.is_empty() => ()
+    }
+  }
 }
 
 /// Init function - used for running start-of-mode behaviours (similar to AutonomousInit, TeleopInit, etc in wpilib)
@@ -93,8 +102,8 @@ async fn my_async_robot() -> RobotResult {
   };
 
   let elevator = Elevator::new(elevator_config, PIDConfig {
-    kp: 6.0, ki: 0.0, kd: 0.5, izone: None
-  });
+    kp: 6.0, kd: 0.5, ..Default::default()
+  }, TrapezoidConstraints::new(1.0, 2.0));
 
   // Run the elevator and simulation. As with all async functions, they don't actually
   // run until they're awaited or spawned.
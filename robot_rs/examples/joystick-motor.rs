@@ -1,13 +1,14 @@
 use std::sync::{atomic::AtomicBool, Arc};
 
-use robot_rs::{start::RobotResult, robot_main, actuators::motors::PWMSparkMax, input::xbox::Xbox, sensors::analog::AnalogInput};
+use robot_rs::{start::RobotResult, robot_main, actuators::motors::PWMSparkMax, input::{xbox::Xbox, shaping::ResponseCurve}, sensors::analog::AnalogInput};
 
 pub fn my_robot(running: Arc<AtomicBool>) -> RobotResult {
   let mut motor = PWMSparkMax::new(0);
   let xbox = Xbox::new(0);
+  let axis = xbox.left_x().shaped().deadband(0.1).curve(ResponseCurve::Squared);
 
   while running.load(std::sync::atomic::Ordering::Relaxed) {
-    let value = xbox.left_x().get();
+    let value = axis.get();
     motor.set_speed(value);
     std::thread::sleep(std::time::Duration::from_millis(20));
   }
@@ -0,0 +1,82 @@
+use wpilib_hal::{HAL_AddressableLEDHandle, HAL_AddressableLEDData, HAL_InitializeAddressableLED, HAL_FreeAddressableLED, HAL_SetAddressableLEDLength, HAL_WriteAddressableLEDData, HAL_StartAddressableLEDOutput, HAL_StopAddressableLEDOutput, HAL_SetAddressableLEDBitTiming, HAL_SetAddressableLEDSyncTime, hal_safe_call};
+
+use crate::{actuators::pwm::PWM, time::now};
+
+use super::{LedBuffer, pattern::LedPattern};
+
+/// Driver for WS2812 (and compatible) addressable LED strips, connected to a PWM port.
+pub struct AddressableLed {
+  handle: HAL_AddressableLEDHandle,
+  length: usize,
+  #[allow(dead_code)]
+  pwm: PWM
+}
+
+impl AddressableLed {
+  pub fn new(port: usize, length: usize) -> Self {
+    let pwm = PWM::new(port);
+    let handle = hal_safe_call!(HAL_InitializeAddressableLED(pwm.handle())).unwrap();
+
+    let mut led = Self { handle, length: 0, pwm };
+    led.set_length(length);
+    led.start();
+    led
+  }
+
+  pub fn length(&self) -> usize { self.length }
+
+  /// Set the length of the strip. Note that this is an expensive operation and should
+  /// only be done rarely.
+  pub fn set_length(&mut self, length: usize) {
+    hal_safe_call!(HAL_SetAddressableLEDLength(self.handle, length as i32)).unwrap();
+    self.length = length;
+  }
+
+  /// Set the bit timings of the strip, in nanoseconds. The defaults are configured for WS2812.
+  pub fn set_bit_timing(&mut self, high_time_0: i32, low_time_0: i32, high_time_1: i32, low_time_1: i32) {
+    hal_safe_call!(HAL_SetAddressableLEDBitTiming(self.handle, high_time_0, low_time_0, high_time_1, low_time_1)).unwrap()
+  }
+
+  /// Set the sync (reset) time of the strip, in microseconds.
+  pub fn set_sync_time(&mut self, sync_time_us: i32) {
+    hal_safe_call!(HAL_SetAddressableLEDSyncTime(self.handle, sync_time_us)).unwrap()
+  }
+
+  pub fn start(&mut self) {
+    hal_safe_call!(HAL_StartAddressableLEDOutput(self.handle)).unwrap()
+  }
+
+  pub fn stop(&mut self) {
+    hal_safe_call!(HAL_StopAddressableLEDOutput(self.handle)).unwrap()
+  }
+
+  /// Write a buffer to the strip. The buffer is truncated (or padded with black) to the length of the strip.
+  pub fn set_data(&mut self, buffer: &LedBuffer) {
+    let mut data = vec![HAL_AddressableLEDData::default(); self.length];
+    for (out, rgb) in data.iter_mut().zip(buffer.iter()) {
+      out.r = rgb.r;
+      out.g = rgb.g;
+      out.b = rgb.b;
+    }
+
+    hal_safe_call!(HAL_WriteAddressableLEDData(self.handle, data.as_ptr(), data.len() as i32)).unwrap()
+  }
+
+  /// Animate a pattern on the strip. This is an async function, so can be spawned as its own task
+  /// and will update the strip every 20ms, forever.
+  pub async fn run<P: LedPattern>(&mut self, pattern: &P) {
+    let mut buffer = LedBuffer::new(self.length);
+    loop {
+      pattern.apply(&mut buffer, now());
+      self.set_data(&buffer);
+      tokio::time::sleep(tokio::time::Duration::from_millis(20)).await;
+    }
+  }
+}
+
+impl Drop for AddressableLed {
+  fn drop(&mut self) {
+    let _ = hal_safe_call!(HAL_StopAddressableLEDOutput(self.handle));
+    unsafe { HAL_FreeAddressableLED(self.handle) };
+  }
+}
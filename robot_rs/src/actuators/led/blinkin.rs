@@ -0,0 +1,188 @@
+use strum::Display;
+
+use crate::{actuators::pwm::{PWM, PWMSpeedController, PWMPeriodMultiplier}, macros::wrapped_traits_nogen};
+
+macro_rules! blinkin_patterns {
+  ($( $name:ident => $value:literal ),* $(,)?) => {
+    /// Patterns for the REV Blinkin, as documented in the Blinkin user's manual.
+    /// Colour 1 and Colour 2 are configured on the Blinkin itself.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+    pub enum BlinkinPattern {
+      $( $name ),*
+    }
+
+    impl BlinkinPattern {
+      pub const ALL: &'static [BlinkinPattern] = &[ $( BlinkinPattern::$name ),* ];
+
+      /// The speed (-1..1) corresponding to the pulse width of this pattern
+      pub fn value(&self) -> f64 {
+        match self {
+          $( BlinkinPattern::$name => $value ),*
+        }
+      }
+    }
+  }
+}
+
+blinkin_patterns! {
+  // Fixed Palette Patterns
+  RainbowRainbowPalette        => -0.99,
+  RainbowPartyPalette          => -0.97,
+  RainbowOceanPalette          => -0.95,
+  RainbowLavaPalette           => -0.93,
+  RainbowForestPalette         => -0.91,
+  RainbowWithGlitter           => -0.89,
+  Confetti                     => -0.87,
+  ShotRed                      => -0.85,
+  ShotBlue                     => -0.83,
+  ShotWhite                    => -0.81,
+  SinelonRainbowPalette        => -0.79,
+  SinelonPartyPalette          => -0.77,
+  SinelonOceanPalette          => -0.75,
+  SinelonLavaPalette           => -0.73,
+  SinelonForestPalette         => -0.71,
+  BpmRainbowPalette            => -0.69,
+  BpmPartyPalette              => -0.67,
+  BpmOceanPalette              => -0.65,
+  BpmLavaPalette               => -0.63,
+  BpmForestPalette             => -0.61,
+  FireMedium                   => -0.59,
+  FireLarge                    => -0.57,
+  TwinklesRainbowPalette       => -0.55,
+  TwinklesPartyPalette         => -0.53,
+  TwinklesOceanPalette         => -0.51,
+  TwinklesLavaPalette          => -0.49,
+  TwinklesForestPalette        => -0.47,
+  ColorWavesRainbowPalette     => -0.45,
+  ColorWavesPartyPalette       => -0.43,
+  ColorWavesOceanPalette       => -0.41,
+  ColorWavesLavaPalette        => -0.39,
+  ColorWavesForestPalette      => -0.37,
+  LarsonScannerRed             => -0.35,
+  LarsonScannerGray            => -0.33,
+  LightChaseRed                => -0.31,
+  LightChaseBlue               => -0.29,
+  LightChaseGray               => -0.27,
+  HeartbeatRed                 => -0.25,
+  HeartbeatBlue                => -0.23,
+  HeartbeatWhite               => -0.21,
+  HeartbeatGray                => -0.19,
+  BreathRed                    => -0.17,
+  BreathBlue                   => -0.15,
+  BreathGray                   => -0.13,
+  StrobeRed                    => -0.11,
+  StrobeBlue                   => -0.09,
+  StrobeGold                   => -0.07,
+  StrobeWhite                  => -0.05,
+
+  // Colour 1 Patterns
+  Color1EndToEndBlendToBlack   => -0.03,
+  Color1LarsonScanner          => -0.01,
+  Color1LightChase             => 0.01,
+  Color1HeartbeatSlow          => 0.03,
+  Color1HeartbeatMedium        => 0.05,
+  Color1HeartbeatFast          => 0.07,
+  Color1BreathSlow             => 0.09,
+  Color1BreathFast             => 0.11,
+  Color1Shot                   => 0.13,
+  Color1Strobe                 => 0.15,
+
+  // Colour 2 Patterns
+  Color2EndToEndBlendToBlack   => 0.17,
+  Color2LarsonScanner          => 0.19,
+  Color2LightChase             => 0.21,
+  Color2HeartbeatSlow          => 0.23,
+  Color2HeartbeatMedium        => 0.25,
+  Color2HeartbeatFast          => 0.27,
+  Color2BreathSlow             => 0.29,
+  Color2BreathFast             => 0.31,
+  Color2Shot                   => 0.33,
+  Color2Strobe                 => 0.35,
+
+  // Colour 1 and 2 Patterns
+  SparkleColor1OnColor2        => 0.37,
+  SparkleColor2OnColor1        => 0.39,
+  GradientColor1And2           => 0.41,
+  BpmColor1And2                => 0.43,
+  EndToEndBlendColor1To2       => 0.45,
+  EndToEndBlend                => 0.47,
+  Color1And2NoBlending         => 0.49,
+  TwinklesColor1And2           => 0.51,
+  ColorWavesColor1And2         => 0.53,
+  SinelonColor1And2            => 0.55,
+
+  // Solid Colours
+  HotPink                      => 0.57,
+  DarkRed                      => 0.59,
+  Red                          => 0.61,
+  RedOrange                    => 0.63,
+  Orange                       => 0.65,
+  Gold                         => 0.67,
+  Yellow                       => 0.69,
+  LawnGreen                    => 0.71,
+  Lime                         => 0.73,
+  DarkGreen                    => 0.75,
+  Green                        => 0.77,
+  BlueGreen                    => 0.79,
+  Aqua                         => 0.81,
+  SkyBlue                      => 0.83,
+  DarkBlue                     => 0.85,
+  Blue                         => 0.87,
+  BlueViolet                   => 0.89,
+  Violet                       => 0.91,
+  White                        => 0.93,
+  Gray                         => 0.95,
+  DarkGray                     => 0.97,
+  Black                        => 0.99,
+}
+
+impl BlinkinPattern {
+  /// Find the pattern closest to the given speed value, if any is within half a step (0.01).
+  pub fn from_value(value: f64) -> Option<Self> {
+    Self::ALL.iter().find(|p| (p.value() - value).abs() < 0.01).copied()
+  }
+}
+
+/// REV Robotics Blinkin LED driver, controlled with a PWM signal.
+pub struct Blinkin(PWMSpeedController);
+
+impl Blinkin {
+  pub fn new(port: usize) -> Self {
+    let mut pwm = PWM::new(port);
+    pwm.set_bounds(2.003, 1.55, 1.50, 1.46, 0.999);
+    pwm.set_period_multiplier(PWMPeriodMultiplier::Multiplier1X);
+    pwm.set_zero_latch();
+    Self(pwm.speed_controller())
+  }
+
+  pub fn set(&mut self, pattern: BlinkinPattern) {
+    self.0.set_speed(pattern.value())
+  }
+
+  pub fn get(&self) -> Option<BlinkinPattern> {
+    BlinkinPattern::from_value(self.0.get_speed())
+  }
+}
+
+wrapped_traits_nogen!(Blinkin, PWMSpeedController);
+
+#[cfg(test)]
+mod test {
+  use approx::assert_relative_eq;
+
+  use super::BlinkinPattern;
+
+  #[test]
+  fn test_pattern_values() {
+    assert_eq!(BlinkinPattern::ALL.len(), 100);
+    assert_relative_eq!(BlinkinPattern::RainbowRainbowPalette.value(), -0.99);
+    assert_relative_eq!(BlinkinPattern::Color1LarsonScanner.value(), -0.01);
+    assert_relative_eq!(BlinkinPattern::Color1LightChase.value(), 0.01);
+    assert_relative_eq!(BlinkinPattern::Black.value(), 0.99);
+
+    for (i, pattern) in BlinkinPattern::ALL.iter().enumerate() {
+      assert_relative_eq!(pattern.value(), -0.99 + 0.02 * i as f64, epsilon = 1e-9);
+      assert_eq!(BlinkinPattern::from_value(pattern.value()), Some(*pattern));
+    }
+  }
+}
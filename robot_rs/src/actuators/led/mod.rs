@@ -0,0 +1,112 @@
+pub mod addressable;
+pub mod blinkin;
+pub mod pattern;
+
+pub use addressable::AddressableLed;
+pub use blinkin::{Blinkin, BlinkinPattern};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Rgb {
+  pub r: u8,
+  pub g: u8,
+  pub b: u8
+}
+
+impl Rgb {
+  pub const BLACK: Rgb = Rgb::new(0, 0, 0);
+  pub const WHITE: Rgb = Rgb::new(255, 255, 255);
+  pub const RED: Rgb = Rgb::new(255, 0, 0);
+  pub const GREEN: Rgb = Rgb::new(0, 255, 0);
+  pub const BLUE: Rgb = Rgb::new(0, 0, 255);
+
+  pub const fn new(r: u8, g: u8, b: u8) -> Self {
+    Self { r, g, b }
+  }
+
+  /// Create a colour from hue (degrees), saturation (0..1) and value (0..1)
+  pub fn from_hsv(hue: f64, saturation: f64, value: f64) -> Self {
+    let h = hue.rem_euclid(360.0) / 60.0;
+    let s = saturation.clamp(0.0, 1.0);
+    let v = value.clamp(0.0, 1.0);
+
+    let c = v * s;
+    let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+    let m = v - c;
+
+    let (r, g, b) = match h as usize {
+      0 => (c, x, 0.0),
+      1 => (x, c, 0.0),
+      2 => (0.0, c, x),
+      3 => (0.0, x, c),
+      4 => (x, 0.0, c),
+      _ => (c, 0.0, x)
+    };
+
+    Self::new(
+      ((r + m) * 255.0).round() as u8,
+      ((g + m) * 255.0).round() as u8,
+      ((b + m) * 255.0).round() as u8
+    )
+  }
+
+  /// Linearly interpolate between this colour and another, where t = 0 is this colour and t = 1 is the other.
+  pub fn lerp(&self, other: &Rgb, t: f64) -> Self {
+    let t = t.clamp(0.0, 1.0);
+    let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+    Self::new(mix(self.r, other.r), mix(self.g, other.g), mix(self.b, other.b))
+  }
+
+  /// Scale the brightness of this colour by a factor (0..1)
+  pub fn scale(&self, factor: f64) -> Self {
+    Rgb::BLACK.lerp(self, factor)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LedBuffer(Vec<Rgb>);
+
+impl LedBuffer {
+  pub fn new(length: usize) -> Self {
+    Self(vec![Rgb::BLACK; length])
+  }
+
+  pub fn fill(&mut self, colour: Rgb) {
+    self.0.fill(colour)
+  }
+}
+
+impl std::ops::Deref for LedBuffer {
+  type Target = [Rgb];
+
+  fn deref(&self) -> &Self::Target {
+    &self.0
+  }
+}
+
+impl std::ops::DerefMut for LedBuffer {
+  fn deref_mut(&mut self) -> &mut Self::Target {
+    &mut self.0
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::Rgb;
+
+  #[test]
+  fn test_hsv() {
+    assert_eq!(Rgb::from_hsv(0.0, 1.0, 1.0), Rgb::RED);
+    assert_eq!(Rgb::from_hsv(120.0, 1.0, 1.0), Rgb::GREEN);
+    assert_eq!(Rgb::from_hsv(240.0, 1.0, 1.0), Rgb::BLUE);
+    assert_eq!(Rgb::from_hsv(360.0, 1.0, 1.0), Rgb::RED);
+    assert_eq!(Rgb::from_hsv(60.0, 0.0, 1.0), Rgb::WHITE);
+  }
+
+  #[test]
+  fn test_lerp() {
+    assert_eq!(Rgb::BLACK.lerp(&Rgb::WHITE, 0.5), Rgb::new(128, 128, 128));
+    assert_eq!(Rgb::RED.lerp(&Rgb::BLUE, 0.0), Rgb::RED);
+    assert_eq!(Rgb::RED.lerp(&Rgb::BLUE, 2.0), Rgb::BLUE);
+    assert_eq!(Rgb::WHITE.scale(0.0), Rgb::BLACK);
+  }
+}
@@ -0,0 +1,293 @@
+use std::{ops::Range, sync::{Arc, RwLock}, f64::consts::PI};
+
+use super::Rgb;
+
+/// A pattern that can be drawn onto a strip (or a section of a strip) of LEDs. `time` is given
+/// in seconds, and is used by animated patterns.
+pub trait LedPattern {
+  fn apply(&self, leds: &mut [Rgb], time: f64);
+}
+
+impl<P: LedPattern + ?Sized> LedPattern for Box<P> {
+  fn apply(&self, leds: &mut [Rgb], time: f64) {
+    (**self).apply(leds, time)
+  }
+}
+
+impl<P: LedPattern + ?Sized> LedPattern for Arc<P> {
+  fn apply(&self, leds: &mut [Rgb], time: f64) {
+    (**self).apply(leds, time)
+  }
+}
+
+// Allows the pattern to be swapped out while it's being animated by AddressableLed::run
+impl<P: LedPattern + ?Sized> LedPattern for RwLock<P> {
+  fn apply(&self, leds: &mut [Rgb], time: f64) {
+    self.read().unwrap().apply(leds, time)
+  }
+}
+
+/* Base Patterns */
+
+pub struct Solid(pub Rgb);
+
+impl LedPattern for Solid {
+  fn apply(&self, leds: &mut [Rgb], _time: f64) {
+    leds.fill(self.0)
+  }
+}
+
+pub struct Gradient {
+  pub from: Rgb,
+  pub to: Rgb
+}
+
+impl LedPattern for Gradient {
+  fn apply(&self, leds: &mut [Rgb], _time: f64) {
+    let n = leds.len();
+    for (i, led) in leds.iter_mut().enumerate() {
+      let t = if n > 1 { i as f64 / (n - 1) as f64 } else { 0.0 };
+      *led = self.from.lerp(&self.to, t);
+    }
+  }
+}
+
+pub struct Rainbow {
+  pub saturation: f64,
+  pub value: f64,
+  /// Speed of the rainbow, in degrees of hue per second
+  pub speed: f64
+}
+
+impl Default for Rainbow {
+  fn default() -> Self {
+    Self { saturation: 1.0, value: 0.5, speed: 90.0 }
+  }
+}
+
+impl LedPattern for Rainbow {
+  fn apply(&self, leds: &mut [Rgb], time: f64) {
+    let n = leds.len();
+    for (i, led) in leds.iter_mut().enumerate() {
+      let hue = time * self.speed + (i as f64) * 360.0 / (n as f64);
+      *led = Rgb::from_hsv(hue, self.saturation, self.value);
+    }
+  }
+}
+
+/// A progress bar, filling the strip from the start according to `progress` (0..1)
+pub struct ProgressBar<F: Fn() -> f64> {
+  pub fill: Rgb,
+  pub empty: Rgb,
+  pub progress: F
+}
+
+impl<F: Fn() -> f64> LedPattern for ProgressBar<F> {
+  fn apply(&self, leds: &mut [Rgb], _time: f64) {
+    let lit = ((self.progress)().clamp(0.0, 1.0) * leds.len() as f64).round() as usize;
+    for (i, led) in leds.iter_mut().enumerate() {
+      *led = if i < lit { self.fill } else { self.empty };
+    }
+  }
+}
+
+/* Modifiers */
+
+pub struct Blink<P: LedPattern> {
+  pattern: P,
+  on_time: f64,
+  off_time: f64
+}
+
+impl<P: LedPattern> LedPattern for Blink<P> {
+  fn apply(&self, leds: &mut [Rgb], time: f64) {
+    if time.rem_euclid(self.on_time + self.off_time) < self.on_time {
+      self.pattern.apply(leds, time)
+    } else {
+      leds.fill(Rgb::BLACK)
+    }
+  }
+}
+
+pub struct Breathe<P: LedPattern> {
+  pattern: P,
+  period: f64
+}
+
+impl<P: LedPattern> LedPattern for Breathe<P> {
+  fn apply(&self, leds: &mut [Rgb], time: f64) {
+    self.pattern.apply(leds, time);
+    let brightness = 0.5 - 0.5 * (2.0 * PI * time / self.period).cos();
+    for led in leds.iter_mut() {
+      *led = led.scale(brightness);
+    }
+  }
+}
+
+pub struct Scrolling<P: LedPattern> {
+  pattern: P,
+  /// Speed of the scroll, in LEDs per second. Negative values scroll towards the start of the strip.
+  speed: f64
+}
+
+impl<P: LedPattern> LedPattern for Scrolling<P> {
+  fn apply(&self, leds: &mut [Rgb], time: f64) {
+    let n = leds.len();
+    if n == 0 { return }
+
+    // Render into a scratch buffer, since the inner pattern may not write every LED and we don't want to
+    // scroll the previous frame along with it.
+    let mut buf = vec![Rgb::BLACK; n];
+    self.pattern.apply(&mut buf, time);
+    let offset = ((time * self.speed).floor() as i64).rem_euclid(n as i64) as usize;
+    buf.rotate_right(offset);
+    leds.copy_from_slice(&buf);
+  }
+}
+
+pub struct Reversed<P: LedPattern>(P);
+
+impl<P: LedPattern> LedPattern for Reversed<P> {
+  fn apply(&self, leds: &mut [Rgb], time: f64) {
+    self.0.apply(leds, time);
+    leds.reverse();
+  }
+}
+
+/* Composition */
+
+/// Apply a pattern only to a section of the strip, leaving the rest of the strip untouched.
+pub struct Segment<P: LedPattern> {
+  pattern: P,
+  range: Range<usize>
+}
+
+impl<P: LedPattern> LedPattern for Segment<P> {
+  fn apply(&self, leds: &mut [Rgb], time: f64) {
+    let end = self.range.end.min(leds.len());
+    let start = self.range.start.min(end);
+    self.pattern.apply(&mut leds[start..end], time)
+  }
+}
+
+/// Apply a pattern only to the LEDs where the mask is true, leaving the rest of the strip untouched.
+pub struct Masked<P: LedPattern> {
+  pattern: P,
+  mask: Vec<bool>
+}
+
+impl<P: LedPattern> LedPattern for Masked<P> {
+  fn apply(&self, leds: &mut [Rgb], time: f64) {
+    let mut buf = leds.to_vec();
+    self.pattern.apply(&mut buf, time);
+    for ((led, new), mask) in leds.iter_mut().zip(buf).zip(self.mask.iter()) {
+      if *mask { *led = new }
+    }
+  }
+}
+
+/// Apply a series of patterns in order, with later patterns drawn on top of earlier ones.
+/// Usually used with Segment or Masked to draw different patterns on different parts of a strip.
+pub struct Layered(pub Vec<Box<dyn LedPattern + Send + Sync>>);
+
+impl LedPattern for Layered {
+  fn apply(&self, leds: &mut [Rgb], time: f64) {
+    for layer in self.0.iter() {
+      layer.apply(leds, time);
+    }
+  }
+}
+
+pub trait LedPatternExt : LedPattern + Sized {
+  fn blink(self, on_time: f64, off_time: f64) -> Blink<Self> {
+    Blink { pattern: self, on_time, off_time }
+  }
+
+  fn breathe(self, period: f64) -> Breathe<Self> {
+    Breathe { pattern: self, period }
+  }
+
+  fn scroll(self, speed: f64) -> Scrolling<Self> {
+    Scrolling { pattern: self, speed }
+  }
+
+  fn reversed(self) -> Reversed<Self> {
+    Reversed(self)
+  }
+
+  fn segment(self, range: Range<usize>) -> Segment<Self> {
+    Segment { pattern: self, range }
+  }
+
+  fn masked(self, mask: Vec<bool>) -> Masked<Self> {
+    Masked { pattern: self, mask }
+  }
+
+  fn boxed(self) -> Box<dyn LedPattern + Send + Sync> where Self: Send + Sync + 'static {
+    Box::new(self)
+  }
+}
+
+impl<P: LedPattern> LedPatternExt for P {}
+
+#[cfg(test)]
+mod test {
+  use crate::actuators::led::{Rgb, LedBuffer};
+
+  use super::{LedPattern, LedPatternExt, Solid, Gradient, ProgressBar, Layered};
+
+  #[test]
+  fn test_gradient() {
+    let mut buf = LedBuffer::new(3);
+    Gradient { from: Rgb::BLACK, to: Rgb::WHITE }.apply(&mut buf, 0.0);
+    assert_eq!(&buf[..], &[Rgb::BLACK, Rgb::new(128, 128, 128), Rgb::WHITE]);
+  }
+
+  #[test]
+  fn test_progress_bar() {
+    let mut buf = LedBuffer::new(4);
+    ProgressBar { fill: Rgb::GREEN, empty: Rgb::RED, progress: || 0.5 }.apply(&mut buf, 0.0);
+    assert_eq!(&buf[..], &[Rgb::GREEN, Rgb::GREEN, Rgb::RED, Rgb::RED]);
+  }
+
+  #[test]
+  fn test_blink() {
+    let mut buf = LedBuffer::new(2);
+    let pattern = Solid(Rgb::BLUE).blink(0.5, 0.25);
+    pattern.apply(&mut buf, 0.25);
+    assert_eq!(&buf[..], &[Rgb::BLUE; 2]);
+    pattern.apply(&mut buf, 0.6);
+    assert_eq!(&buf[..], &[Rgb::BLACK; 2]);
+    pattern.apply(&mut buf, 0.8);
+    assert_eq!(&buf[..], &[Rgb::BLUE; 2]);
+  }
+
+  #[test]
+  fn test_scroll() {
+    let mut buf = LedBuffer::new(4);
+    let pattern = Solid(Rgb::RED).segment(0..1).scroll(2.0);
+    pattern.apply(&mut buf, 1.0);
+    assert_eq!(&buf[..], &[Rgb::BLACK, Rgb::BLACK, Rgb::RED, Rgb::BLACK]);
+  }
+
+  #[test]
+  fn test_scroll_reused_buffer() {
+    let mut buf = LedBuffer::new(4);
+    let pattern = Solid(Rgb::RED).segment(0..1).scroll(1.0);
+    pattern.apply(&mut buf, 1.0);
+    pattern.apply(&mut buf, 2.0);
+    assert_eq!(&buf[..], &[Rgb::BLACK, Rgb::BLACK, Rgb::RED, Rgb::BLACK]);
+  }
+
+  #[test]
+  fn test_layered() {
+    let mut buf = LedBuffer::new(4);
+    let pattern = Layered(vec![
+      Solid(Rgb::RED).boxed(),
+      Solid(Rgb::GREEN).segment(2..10).boxed(),
+      Solid(Rgb::BLUE).masked(vec![true, false]).boxed()
+    ]);
+    pattern.apply(&mut buf, 0.0);
+    assert_eq!(&buf[..], &[Rgb::BLUE, Rgb::RED, Rgb::GREEN, Rgb::GREEN]);
+  }
+}
@@ -1,2 +1,4 @@
+pub mod led;
 pub mod motors;
-pub mod pwm;
\ No newline at end of file
+pub mod pwm;
+pub mod relay;
\ No newline at end of file
@@ -1,5 +1,7 @@
 use crate::macros::{wrapped_traits, wrapped_traits_nogen};
 use crate::actuators::pwm::{PWM, PWMSpeedController};
+use crate::sensors::{digital::DigitalInput, distance::DistanceSource};
+use crate::types::MinMax;
 use wpilib_hal::{hal_safe_call, HAL_GetVinVoltage};
 
 pub trait MotorController {
@@ -31,8 +33,70 @@ impl<M: MotorController> MotorController for ClampedMotor<M> {
   }
 }
 
+#[derive(Default)]
+struct MotorLimits {
+  forward: Option<Box<dyn DigitalInput + Send + Sync>>,
+  reverse: Option<Box<dyn DigitalInput + Send + Sync>>,
+  soft: Option<(Box<dyn DistanceSource + Send + Sync>, MinMax<f64>)>
+}
+
+/// A motor that refuses to drive past its limits. Positive voltage is considered to be the forward
+/// direction. Once a limit is reached, voltage in that direction is zeroed, but the motor is still
+/// free to move away from the limit. Limits are evaluated on each call to set_voltage, so the
+/// voltage should be set every loop.
+pub struct LimitedMotor<M: MotorController>(pub M, MotorLimits);
+
+impl<M: MotorController> LimitedMotor<M> {
+  pub fn new(motor: M) -> Self {
+    Self(motor, MotorLimits::default())
+  }
+
+  /// Add a forward limit switch, which is true when the limit is reached
+  pub fn with_forward_limit<D: DigitalInput + Send + Sync + 'static>(mut self, limit: D) -> Self {
+    self.1.forward = Some(Box::new(limit));
+    self
+  }
+
+  /// Add a reverse limit switch, which is true when the limit is reached
+  pub fn with_reverse_limit<D: DigitalInput + Send + Sync + 'static>(mut self, limit: D) -> Self {
+    self.1.reverse = Some(Box::new(limit));
+    self
+  }
+
+  /// Add soft limits, given as a range of distances the mechanism is allowed to move within
+  pub fn with_soft_limits<S: DistanceSource + Send + Sync + 'static>(mut self, source: S, range: MinMax<f64>) -> Self {
+    self.1.soft = Some((Box::new(source), range));
+    self
+  }
+
+  pub fn is_forward_limited(&self) -> bool {
+    self.1.forward.as_ref().map(|x| x.get()).unwrap_or(false)
+      || self.1.soft.as_ref().map(|(source, range)| source.get_distance() >= range.max).unwrap_or(false)
+  }
+
+  pub fn is_reverse_limited(&self) -> bool {
+    self.1.reverse.as_ref().map(|x| x.get()).unwrap_or(false)
+      || self.1.soft.as_ref().map(|(source, range)| source.get_distance() <= range.min).unwrap_or(false)
+  }
+}
+
+impl<M: MotorController> MotorController for LimitedMotor<M> {
+  fn set_voltage(&mut self, voltage: f64) {
+    if (voltage > 0.0 && self.is_forward_limited()) || (voltage < 0.0 && self.is_reverse_limited()) {
+      self.0.set_voltage(0.0)
+    } else {
+      self.0.set_voltage(voltage)
+    }
+  }
+
+  fn get_set_voltage(&self) -> f64 {
+    self.0.get_set_voltage()
+  }
+}
+
 wrapped_traits!(MotorController, InvertMotor);
 wrapped_traits!(MotorController, ClampedMotor);
+wrapped_traits!(MotorController, LimitedMotor);
 
 macro_rules! pwm_motor_impl {
   ($name:ident) => {
@@ -70,7 +134,21 @@ pwm_motor_impl!(PWMTalonFX);
 mod test {
   use approx::assert_relative_eq;
 
-  use super::{PWMSparkMax, MotorController, InvertMotor, ClampedMotor};
+  use std::sync::{Arc, atomic::{AtomicBool, Ordering}, RwLock};
+
+  use crate::{sensors::{digital::DigitalInput, distance::DistanceSource}, types::MinMax};
+
+  use super::{PWMSparkMax, MotorController, InvertMotor, ClampedMotor, LimitedMotor};
+
+  struct TestSwitch(Arc<AtomicBool>);
+  impl DigitalInput for TestSwitch {
+    fn get(&self) -> bool { self.0.load(Ordering::Relaxed) }
+  }
+
+  struct TestDistance(Arc<RwLock<f64>>);
+  impl DistanceSource for TestDistance {
+    fn get_distance(&self) -> f64 { *self.0.read().unwrap() }
+  }
 
   #[test]
   fn test_pwm_motor_controller() {
@@ -100,4 +178,49 @@ mod test {
     clamped.set_voltage(-6.0);
     assert_relative_eq!(clamped.get_set_voltage(), -5.0);
   }
+
+  #[test]
+  fn test_limited_motor_switches() {
+    let forward = Arc::new(AtomicBool::new(false));
+    let reverse = Arc::new(AtomicBool::new(false));
+    let mut limited = LimitedMotor::new(PWMSparkMax::new(3))
+      .with_forward_limit(TestSwitch(forward.clone()))
+      .with_reverse_limit(TestSwitch(reverse.clone()));
+
+    limited.set_voltage(6.0);
+    assert_relative_eq!(limited.get_set_voltage(), 6.0);
+
+    forward.store(true, Ordering::Relaxed);
+    limited.set_voltage(6.0);
+    assert_relative_eq!(limited.get_set_voltage(), 0.0);
+    limited.set_voltage(-6.0);
+    assert_relative_eq!(limited.get_set_voltage(), -6.0);
+
+    forward.store(false, Ordering::Relaxed);
+    reverse.store(true, Ordering::Relaxed);
+    limited.set_voltage(-6.0);
+    assert_relative_eq!(limited.get_set_voltage(), 0.0);
+    limited.set_voltage(6.0);
+    assert_relative_eq!(limited.get_set_voltage(), 6.0);
+  }
+
+  #[test]
+  fn test_limited_motor_soft() {
+    let distance = Arc::new(RwLock::new(0.5));
+    let mut limited = LimitedMotor::new(PWMSparkMax::new(4))
+      .with_soft_limits(TestDistance(distance.clone()), MinMax::new(0.0, 1.0));
+
+    limited.set_voltage(3.0);
+    assert_relative_eq!(limited.get_set_voltage(), 3.0);
+
+    *distance.write().unwrap() = 1.1;
+    limited.set_voltage(3.0);
+    assert_relative_eq!(limited.get_set_voltage(), 0.0);
+    limited.set_voltage(-3.0);
+    assert_relative_eq!(limited.get_set_voltage(), -3.0);
+
+    *distance.write().unwrap() = -0.1;
+    limited.set_voltage(-3.0);
+    assert_relative_eq!(limited.get_set_voltage(), 0.0);
+  }
 }
\ No newline at end of file
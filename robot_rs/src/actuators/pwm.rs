@@ -23,6 +23,8 @@ impl PWM {
 
   pub fn port(&self) -> usize { self.port }
 
+  pub(crate) fn handle(&self) -> HAL_DigitalHandle { self.handle }
+
   pub fn set_raw(&mut self, value: u16) {
     hal_safe_call!(HAL_SetPWMRaw(self.handle, value as i32)).unwrap();
   }
@@ -0,0 +1,173 @@
+use wpilib_hal::{HAL_RelayHandle, HAL_InitializeRelayPort, HAL_GetPort, HAL_FreeRelayPort, HAL_SetRelay, HAL_GetRelay, hal_safe_call};
+
+use crate::{sensors::digital::{DigitalInput, DigitalOutput}, ds::report_error};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelayDirection {
+  Both,
+  Forward,
+  Reverse
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelayValue {
+  Off,
+  On,
+  Forward,
+  Reverse
+}
+
+/// A single channel (forward or reverse) of a relay port.
+pub struct RelayChannel {
+  handle: HAL_RelayHandle
+}
+
+impl RelayChannel {
+  fn new(port: usize, forward: bool) -> Self {
+    let handle = hal_safe_call!(HAL_InitializeRelayPort(HAL_GetPort(port as i32), forward as i32, "Relay::new".as_ptr() as *const i8)).unwrap();
+    Self { handle }
+  }
+}
+
+impl DigitalInput for RelayChannel {
+  fn get(&self) -> bool {
+    hal_safe_call!(HAL_GetRelay(self.handle)).unwrap() != 0
+  }
+}
+
+impl DigitalOutput for RelayChannel {
+  fn set(&mut self, value: bool) {
+    hal_safe_call!(HAL_SetRelay(self.handle, value as i32)).unwrap()
+  }
+}
+
+impl Drop for RelayChannel {
+  fn drop(&mut self) {
+    let _ = hal_safe_call!(HAL_SetRelay(self.handle, 0));
+    unsafe { HAL_FreeRelayPort(self.handle) };
+  }
+}
+
+pub struct Relay {
+  port: usize,
+  direction: RelayDirection,
+  forward: Option<RelayChannel>,
+  reverse: Option<RelayChannel>
+}
+
+impl Relay {
+  pub fn new(port: usize, direction: RelayDirection) -> Self {
+    let forward = match direction {
+      RelayDirection::Both | RelayDirection::Forward => Some(RelayChannel::new(port, true)),
+      RelayDirection::Reverse => None
+    };
+    let reverse = match direction {
+      RelayDirection::Both | RelayDirection::Reverse => Some(RelayChannel::new(port, false)),
+      RelayDirection::Forward => None
+    };
+
+    Self { port, direction, forward, reverse }
+  }
+
+  pub fn port(&self) -> usize { self.port }
+  pub fn direction(&self) -> RelayDirection { self.direction }
+
+  /// Set the value of the relay. Forward and Reverse are only valid for relays configured
+  /// in both directions, in which case they drive one channel and switch the other off.
+  /// For single-direction relays, On and Forward / Reverse (matching the configured direction)
+  /// are equivalent. Setting the opposite direction on a single-direction relay reports an error
+  /// to the Driver Station and leaves the relay unchanged.
+  pub fn set(&mut self, value: RelayValue) {
+    let (fwd, rev) = match (self.direction, value) {
+      (_, RelayValue::Off) => (false, false),
+      (_, RelayValue::On) => (true, true),
+      (RelayDirection::Both, RelayValue::Forward) => (true, false),
+      (RelayDirection::Both, RelayValue::Reverse) => (false, true),
+      (RelayDirection::Forward, RelayValue::Forward) => (true, false),
+      (RelayDirection::Reverse, RelayValue::Reverse) => (false, true),
+      (direction, value) => {
+        report_error(&format!("Relay {}: cannot set {:?} on a relay configured for {:?}", self.port, value, direction));
+        return
+      }
+    };
+
+    if let Some(forward) = self.forward.as_mut() { forward.set(fwd) }
+    if let Some(reverse) = self.reverse.as_mut() { reverse.set(rev) }
+  }
+
+  pub fn get(&self) -> RelayValue {
+    let fwd = self.forward.as_ref().map(|x| x.get()).unwrap_or(false);
+    let rev = self.reverse.as_ref().map(|x| x.get()).unwrap_or(false);
+
+    match (self.direction, fwd, rev) {
+      (RelayDirection::Both, true, true) => RelayValue::On,
+      (RelayDirection::Both, true, false) => RelayValue::Forward,
+      (RelayDirection::Both, false, true) => RelayValue::Reverse,
+      (RelayDirection::Forward, true, _) => RelayValue::On,
+      (RelayDirection::Reverse, _, true) => RelayValue::On,
+      _ => RelayValue::Off
+    }
+  }
+
+  pub fn forward(&mut self) -> Option<&mut RelayChannel> {
+    self.forward.as_mut()
+  }
+
+  pub fn reverse(&mut self) -> Option<&mut RelayChannel> {
+    self.reverse.as_mut()
+  }
+
+  /// Split the relay into its forward and reverse channels, so each can be used as its
+  /// own DigitalOutput.
+  pub fn split(self) -> (Option<RelayChannel>, Option<RelayChannel>) {
+    (self.forward, self.reverse)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use crate::sensors::digital::{DigitalInput, DigitalOutput};
+
+  use super::{Relay, RelayDirection, RelayValue};
+
+  #[test]
+  fn test_relay_both() {
+    let mut relay = Relay::new(0, RelayDirection::Both);
+    assert_eq!(relay.get(), RelayValue::Off);
+    relay.set(RelayValue::Forward);
+    assert_eq!(relay.get(), RelayValue::Forward);
+    relay.set(RelayValue::Reverse);
+    assert_eq!(relay.get(), RelayValue::Reverse);
+    relay.set(RelayValue::On);
+    assert_eq!(relay.get(), RelayValue::On);
+    relay.set(RelayValue::Off);
+    assert_eq!(relay.get(), RelayValue::Off);
+  }
+
+  #[test]
+  fn test_relay_single_direction() {
+    let mut relay = Relay::new(1, RelayDirection::Forward);
+    assert!(relay.reverse().is_none());
+    relay.set(RelayValue::Forward);
+    assert_eq!(relay.get(), RelayValue::On);
+    relay.set(RelayValue::Off);
+    assert_eq!(relay.get(), RelayValue::Off);
+  }
+
+  #[test]
+  fn test_relay_direction_mismatch() {
+    let mut relay = Relay::new(3, RelayDirection::Reverse);
+    relay.set(RelayValue::On);
+    relay.set(RelayValue::Forward);
+    assert_eq!(relay.get(), RelayValue::On);
+  }
+
+  #[test]
+  fn test_relay_channels() {
+    let (forward, reverse) = Relay::new(2, RelayDirection::Both).split();
+    let (mut forward, reverse) = (forward.unwrap(), reverse.unwrap());
+    forward.set(true);
+    assert!(forward.get());
+    assert!(!reverse.get());
+  }
+}
@@ -0,0 +1,85 @@
+use std::{cell::RefCell, sync::Arc};
+
+use crate::sensors::digital::DigitalInput;
+
+use super::Behaviour;
+
+pub type BehaviourFactory = Box<dyn FnMut() -> Arc<RefCell<dyn Behaviour>>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BindingKind {
+  /// Schedule a new behaviour when the input becomes true
+  OnPress,
+  /// Schedule a new behaviour when the input becomes false
+  OnRelease,
+  /// Schedule a new behaviour when the input becomes true, and interrupt it when the input becomes false
+  WhileHeld,
+  /// Schedule a new behaviour when the input becomes true, or interrupt it if it's still running
+  ToggleOnPress
+}
+
+pub(crate) enum BindingAction {
+  Nothing,
+  Schedule(Arc<RefCell<dyn Behaviour>>),
+  Interrupt(Arc<RefCell<dyn Behaviour>>)
+}
+
+/// Binds a DigitalInput (e.g. a button) to a behaviour, which is created by the factory each time it's triggered.
+pub struct Binding {
+  kind: BindingKind,
+  input: Box<dyn DigitalInput>,
+  factory: BehaviourFactory,
+  last: bool,
+  active: Option<Arc<RefCell<dyn Behaviour>>>
+}
+
+impl Binding {
+  pub fn new<D, F>(kind: BindingKind, input: D, factory: F) -> Self
+  where
+    D: DigitalInput + 'static,
+    F: FnMut() -> Arc<RefCell<dyn Behaviour>> + 'static
+  {
+    let last = input.get();
+    Self {
+      kind,
+      input: Box::new(input),
+      factory: Box::new(factory),
+      last,
+      active: None
+    }
+  }
+
+  pub fn kind(&self) -> BindingKind { self.kind }
+
+  pub(crate) fn poll<F: Fn(&Arc<RefCell<dyn Behaviour>>) -> bool>(&mut self, is_running: F) -> BindingAction {
+    let value = self.input.get();
+    let (rising, falling) = (value && !self.last, !value && self.last);
+    self.last = value;
+
+    if self.active.as_ref().map(|x| !is_running(x)).unwrap_or(false) {
+      self.active = None;
+    }
+
+    match (self.kind, rising, falling) {
+      (BindingKind::OnPress, true, _) | (BindingKind::OnRelease, _, true) => BindingAction::Schedule((self.factory)()),
+      (BindingKind::WhileHeld, true, _) => {
+        let behaviour = (self.factory)();
+        self.active = Some(behaviour.clone());
+        BindingAction::Schedule(behaviour)
+      },
+      (BindingKind::WhileHeld, _, true) => match self.active.take() {
+        Some(behaviour) => BindingAction::Interrupt(behaviour),
+        None => BindingAction::Nothing
+      },
+      (BindingKind::ToggleOnPress, true, _) => match self.active.take() {
+        Some(behaviour) => BindingAction::Interrupt(behaviour),
+        None => {
+          let behaviour = (self.factory)();
+          self.active = Some(behaviour.clone());
+          BindingAction::Schedule(behaviour)
+        }
+      },
+      _ => BindingAction::Nothing
+    }
+  }
+}
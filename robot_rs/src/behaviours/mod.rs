@@ -1,7 +1,14 @@
+pub mod bindings;
+
 use std::{collections::HashSet, cell::RefCell, sync::Arc};
 
+use log::warn;
 use mockall::automock;
 
+use crate::sensors::digital::DigitalInput;
+
+use self::bindings::{Binding, BindingKind, BindingAction};
+
 pub trait HasBehaviour {
   fn behaviour_key(&self) -> String;
 }
@@ -47,16 +54,46 @@ pub struct ContextualisedBehaviour {
 }
 
 pub struct BehaviourScheduler {
-  running: Vec<ContextualisedBehaviour>
+  running: Vec<ContextualisedBehaviour>,
+  bindings: Vec<Binding>
 }
 
 impl BehaviourScheduler {
   pub fn new() -> Self {
     Self {
-      running: vec![]
+      running: vec![],
+      bindings: vec![]
     }
   }
 
+  pub fn bind(&mut self, binding: Binding) {
+    self.bindings.push(binding);
+  }
+
+  pub fn on_press<D, F>(&mut self, input: D, factory: F)
+  where D: DigitalInput + 'static, F: FnMut() -> Arc<RefCell<dyn Behaviour>> + 'static {
+    self.bind(Binding::new(BindingKind::OnPress, input, factory))
+  }
+
+  pub fn on_release<D, F>(&mut self, input: D, factory: F)
+  where D: DigitalInput + 'static, F: FnMut() -> Arc<RefCell<dyn Behaviour>> + 'static {
+    self.bind(Binding::new(BindingKind::OnRelease, input, factory))
+  }
+
+  pub fn while_held<D, F>(&mut self, input: D, factory: F)
+  where D: DigitalInput + 'static, F: FnMut() -> Arc<RefCell<dyn Behaviour>> + 'static {
+    self.bind(Binding::new(BindingKind::WhileHeld, input, factory))
+  }
+
+  pub fn toggle_on_press<D, F>(&mut self, input: D, factory: F)
+  where D: DigitalInput + 'static, F: FnMut() -> Arc<RefCell<dyn Behaviour>> + 'static {
+    self.bind(Binding::new(BindingKind::ToggleOnPress, input, factory))
+  }
+
+  pub fn is_running(&self, behaviour: &Arc<RefCell<dyn Behaviour>>) -> bool {
+    self.running.iter().any(|bhvr| Arc::ptr_eq(&bhvr.behaviour, behaviour))
+  }
+
   pub fn schedule(&mut self, behaviour: Arc<RefCell<dyn Behaviour>>) -> anyhow::Result<()> {
     let mut contextualised = ContextualisedBehaviour {
       state: BehaviourState::Constructed,
@@ -99,13 +136,40 @@ impl BehaviourScheduler {
     Ok(())
   }
 
+  pub fn interrupt_behaviour(&mut self, behaviour: &Arc<RefCell<dyn Behaviour>>) {
+    for bhvr in &mut self.running {
+      if Arc::ptr_eq(&bhvr.behaviour, behaviour) {
+        bhvr.state = BehaviourState::Interrupted;
+      }
+    }
+  }
+
   pub fn interrupt_all(&mut self) {
     for bhvr in &mut self.running {
       bhvr.state = BehaviourState::Interrupted;
     }
   }
 
+  fn update_bindings(&mut self) {
+    let running = &self.running;
+    let actions: Vec<BindingAction> = self.bindings.iter_mut()
+      .map(|binding| binding.poll(|b| running.iter().any(|bhvr| Arc::ptr_eq(&bhvr.behaviour, b))))
+      .collect();
+
+    for action in actions {
+      match action {
+        BindingAction::Schedule(behaviour) => if let Err(e) = self.schedule(behaviour) {
+          warn!("Could not schedule bound behaviour: {}", e);
+        },
+        BindingAction::Interrupt(behaviour) => self.interrupt_behaviour(&behaviour),
+        BindingAction::Nothing => ()
+      }
+    }
+  }
+
   pub fn update(&mut self, dt: f64) -> anyhow::Result<()> {
+    self.update_bindings();
+
     self.running.retain_mut(|bhvr| {
       let mut behaviour = bhvr.behaviour.borrow_mut();
       if let BehaviourState::Constructed = bhvr.state {
@@ -145,11 +209,67 @@ impl BehaviourScheduler {
 
 #[cfg(test)]
 mod test {
-  use std::{cell::RefCell, sync::Arc};
+  use std::{cell::{RefCell, Cell}, sync::Arc, rc::Rc};
 
   use approx::relative_eq;
 
-  use super::{MockBehaviour, BehaviourState, BehaviourScheduler};
+  use crate::sensors::digital::DigitalInput;
+
+  use super::{MockBehaviour, BehaviourState, BehaviourScheduler, Behaviour};
+
+  struct TestButton(Rc<Cell<bool>>);
+  impl DigitalInput for TestButton {
+    fn get(&self) -> bool { self.0.get() }
+  }
+
+  fn once_factory(mock: MockBehaviour) -> impl FnMut() -> Arc<RefCell<dyn Behaviour>> {
+    let mut mock = Some(mock);
+    move || -> Arc<RefCell<dyn Behaviour>> { Arc::new(RefCell::new(mock.take().expect("Behaviour created more than once"))) }
+  }
+
+  fn running_mock(n_updates: usize) -> MockBehaviour {
+    let mut mock = MockBehaviour::new();
+    mock.expect_name().return_const("MockBehaviour".to_owned());
+    mock.expect_init().once().return_once(move |_| Ok(()));
+    mock.expect_on_started().once().return_once(move || Ok(super::BehaviourRequest::Continue));
+    mock.expect_on_update().times(n_updates).returning(move |_, _| Ok(super::BehaviourRequest::Continue));
+    mock.expect_on_stopped()
+      .once()
+      .withf(move |state, &total_time| relative_eq!(total_time, n_updates as f64 * 0.05) && matches!(state, BehaviourState::Interrupted))
+      .return_once(move |_, _| Ok(()));
+    mock
+  }
+
+  #[test]
+  fn test_binding_while_held() -> anyhow::Result<()> {
+    let button = Rc::new(Cell::new(false));
+    let mut scheduler = BehaviourScheduler::new();
+    scheduler.while_held(TestButton(button.clone()), once_factory(running_mock(3)));
+
+    for _ in 0..3 { scheduler.update(0.05)?; }    // Not pressed, shouldn't be scheduled
+    button.set(true);
+    for _ in 0..3 { scheduler.update(0.05)?; }    // Should call 3 times
+    button.set(false);
+    for _ in 0..3 { scheduler.update(0.05)?; }    // Interrupted on release, shouldn't call again
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_binding_toggle() -> anyhow::Result<()> {
+    let button = Rc::new(Cell::new(false));
+    let mut scheduler = BehaviourScheduler::new();
+    scheduler.toggle_on_press(TestButton(button.clone()), once_factory(running_mock(4)));
+
+    button.set(true);
+    for _ in 0..2 { scheduler.update(0.05)?; }    // Scheduled on press
+    button.set(false);
+    for _ in 0..2 { scheduler.update(0.05)?; }    // Keeps running after release
+    button.set(true);
+    for _ in 0..3 { scheduler.update(0.05)?; }    // Interrupted on the second press
+
+    Ok(())
+  }
   
   #[test]
   fn test_scheduler_finish() -> anyhow::Result<()> {
@@ -0,0 +1,102 @@
+use nt4_rs::{nt, types::Value};
+
+#[derive(Default, Debug, Clone)]
+pub struct BangBangMeasurement {
+  pub time: f64,
+  pub setpoint: f64,
+  pub process_variable: f64,
+  pub error: f64,
+  pub output: f64
+}
+
+/// A bang-bang controller, which outputs 1 when below the setpoint and 0 otherwise. Intended for high-inertia
+/// mechanisms like flywheels, where it gives the fastest possible spin-up and recovery. It should only be used
+/// where the mechanism is allowed to coast down (never drive it in reverse), and is usually combined with a
+/// feedforward (e.g. SimpleMotorFeedforward with kS and kV) so the output isn't just alternating between full and off:
+///
+/// `voltage = bang_bang.calculate(speed, now()) * 12.0 + 0.9 * feedforward.calculate(setpoint, 0.0)`
+#[derive(Clone, Debug)]
+pub struct BangBangController {
+  tolerance: f64,
+  setpoint: f64,
+  last: Option<BangBangMeasurement>
+}
+
+impl BangBangController {
+  pub fn new(setpoint: f64, tolerance: f64) -> Self {
+    Self { tolerance, setpoint, last: None }
+  }
+
+  pub fn last(&self) -> Option<&BangBangMeasurement> {
+    self.last.as_ref()
+  }
+
+  pub fn set_setpoint(&mut self, setpoint: f64) {
+    self.setpoint = setpoint;
+  }
+
+  pub fn get_setpoint(&self) -> f64 {
+    self.setpoint
+  }
+
+  pub fn set_tolerance(&mut self, tolerance: f64) {
+    self.tolerance = tolerance;
+  }
+
+  pub fn get_tolerance(&self) -> f64 {
+    self.tolerance
+  }
+
+  pub fn calculate(&mut self, pv: f64, time: f64) -> f64 {
+    let output = if pv < self.setpoint { 1.0 } else { 0.0 };
+    self.last = Some(BangBangMeasurement { time, setpoint: self.setpoint, process_variable: pv, error: self.setpoint - pv, output });
+    output
+  }
+
+  /// Whether the last measurement was within the tolerance of the current setpoint
+  pub fn at_setpoint(&self) -> bool {
+    match &self.last {
+      Some(last) => (self.setpoint - last.process_variable).abs() <= self.tolerance,
+      None => false
+    }
+  }
+
+  pub fn nt_update(&mut self, path: &str) {
+    let basepath = path.to_owned() + "/bangbang";
+    let tolerance_path = format!("{}/config/tolerance", basepath);
+
+    match nt!(read &tolerance_path).data {
+      Value::Unassigned => nt!(&tolerance_path, self.tolerance).unwrap(),
+      Value::Double(val) => self.tolerance = val,
+      Value::Float(val) => self.tolerance = val as f64,
+      _ => ()
+    }
+
+    let last = self.last().cloned().unwrap_or_default();
+    nt!(&format!("{}/{}", basepath, "time"), last.time).unwrap();
+    nt!(&format!("{}/{}", basepath, "setpoint"), last.setpoint).unwrap();
+    nt!(&format!("{}/{}", basepath, "process_variable"), last.process_variable).unwrap();
+    nt!(&format!("{}/{}", basepath, "error"), last.error).unwrap();
+    nt!(&format!("{}/{}", basepath, "output"), last.output).unwrap();
+    nt!(&format!("{}/{}", basepath, "at_setpoint"), self.at_setpoint()).unwrap();
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::BangBangController;
+
+  #[test]
+  fn test_bang_bang() {
+    let mut bb = BangBangController::new(100.0, 5.0);
+    assert!(!bb.at_setpoint());
+    assert_eq!(bb.calculate(50.0, 0.0), 1.0);
+    assert!(!bb.at_setpoint());
+    assert_eq!(bb.calculate(97.0, 0.1), 1.0);
+    assert!(bb.at_setpoint());
+    assert_eq!(bb.calculate(102.0, 0.2), 0.0);
+    assert!(bb.at_setpoint());
+    bb.set_setpoint(200.0);
+    assert!(!bb.at_setpoint());
+  }
+}
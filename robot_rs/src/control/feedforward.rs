@@ -0,0 +1,204 @@
+use crate::models::DcMotor;
+
+const GRAVITY: f64 = 9.81;
+
+// Static friction opposes motion, so has no direction when stopped (unlike f64::signum)
+fn sign(x: f64) -> f64 {
+  if x > 0.0 { 1.0 } else if x < 0.0 { -1.0 } else { 0.0 }
+}
+
+/// Feedforward for a motor driving a mechanism with no gravity load (e.g. a flywheel or drivetrain).
+/// V = kS * sign(v) + kV * v + kA * a
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SimpleMotorFeedforward {
+  pub ks: f64,
+  pub kv: f64,
+  pub ka: f64
+}
+
+impl SimpleMotorFeedforward {
+  pub fn new(ks: f64, kv: f64, ka: f64) -> Self {
+    Self { ks, kv, ka }
+  }
+
+  /// Derive gains from a motor model, driving a load with the given moment of inertia, with velocity in rad/s.
+  /// Static friction (kS) isn't modelled, so should be characterised separately.
+  pub fn from_motor(motor: &DcMotor, moi: f64) -> Self {
+    Self::new(0.0, 1.0 / motor.kw_rad_per_sec(), motor.voltage(moi, 0.0))
+  }
+
+  pub fn calculate(&self, velocity: f64, acceleration: f64) -> f64 {
+    self.ks * sign(velocity) + self.kv * velocity + self.ka * acceleration
+  }
+
+  /// The fastest velocity we can reach while accelerating at the given rate, with the given voltage available
+  pub fn max_achievable_velocity(&self, max_voltage: f64, acceleration: f64) -> f64 {
+    (max_voltage - self.ks - self.ka * acceleration) / self.kv
+  }
+
+  pub fn min_achievable_velocity(&self, max_voltage: f64, acceleration: f64) -> f64 {
+    (-max_voltage + self.ks - self.ka * acceleration) / self.kv
+  }
+
+  /// The fastest we can accelerate while travelling at the given velocity, with the given voltage available
+  pub fn max_achievable_acceleration(&self, max_voltage: f64, velocity: f64) -> f64 {
+    (max_voltage - self.ks * sign(velocity) - self.kv * velocity) / self.ka
+  }
+
+  pub fn min_achievable_acceleration(&self, max_voltage: f64, velocity: f64) -> f64 {
+    self.max_achievable_acceleration(-max_voltage, velocity)
+  }
+}
+
+/// Feedforward for an elevator, including the constant voltage needed to hold it up against gravity.
+/// V = kS * sign(v) + kG + kV * v + kA * a
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ElevatorFeedforward {
+  pub ks: f64,
+  pub kg: f64,
+  pub kv: f64,
+  pub ka: f64
+}
+
+impl ElevatorFeedforward {
+  pub fn new(ks: f64, kg: f64, kv: f64, ka: f64) -> Self {
+    Self { ks, kg, kv, ka }
+  }
+
+  /// Derive gains from a motor model (including its reduction) driving a carriage of the given mass (kg) through
+  /// a spool of the given radius (m), with velocity in m/s.
+  pub fn from_motor(motor: &DcMotor, mass: f64, spool_radius: f64) -> Self {
+    Self::new(
+      0.0,
+      motor.voltage(mass * GRAVITY * spool_radius, 0.0),
+      1.0 / (motor.kw_rad_per_sec() * spool_radius),
+      motor.voltage(mass * spool_radius, 0.0)
+    )
+  }
+
+  pub fn calculate(&self, velocity: f64, acceleration: f64) -> f64 {
+    self.ks * sign(velocity) + self.kg + self.kv * velocity + self.ka * acceleration
+  }
+
+  pub fn max_achievable_velocity(&self, max_voltage: f64, acceleration: f64) -> f64 {
+    (max_voltage - self.ks - self.kg - self.ka * acceleration) / self.kv
+  }
+
+  pub fn min_achievable_velocity(&self, max_voltage: f64, acceleration: f64) -> f64 {
+    (-max_voltage + self.ks - self.kg - self.ka * acceleration) / self.kv
+  }
+
+  pub fn max_achievable_acceleration(&self, max_voltage: f64, velocity: f64) -> f64 {
+    (max_voltage - self.ks * sign(velocity) - self.kg - self.kv * velocity) / self.ka
+  }
+
+  pub fn min_achievable_acceleration(&self, max_voltage: f64, velocity: f64) -> f64 {
+    self.max_achievable_acceleration(-max_voltage, velocity)
+  }
+}
+
+/// Feedforward for an arm, where the voltage needed to hold it up against gravity depends on its angle.
+/// Angles are in radians, measured from horizontal.
+/// V = kS * sign(ω) + kG * cos(θ) + kV * ω + kA * α
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ArmFeedforward {
+  pub ks: f64,
+  pub kg: f64,
+  pub kv: f64,
+  pub ka: f64
+}
+
+impl ArmFeedforward {
+  pub fn new(ks: f64, kg: f64, kv: f64, ka: f64) -> Self {
+    Self { ks, kg, kv, ka }
+  }
+
+  /// Derive gains from a motor model (including its reduction) driving an arm of the given mass (kg), with its centre of
+  /// mass the given distance (m) from the pivot and the given moment of inertia about the pivot (kg m^2), with angular
+  /// velocity in rad/s.
+  pub fn from_motor(motor: &DcMotor, mass: f64, com_distance: f64, moi: f64) -> Self {
+    Self::new(
+      0.0,
+      motor.voltage(mass * GRAVITY * com_distance, 0.0),
+      1.0 / motor.kw_rad_per_sec(),
+      motor.voltage(moi, 0.0)
+    )
+  }
+
+  pub fn calculate(&self, angle: f64, velocity: f64, acceleration: f64) -> f64 {
+    self.ks * sign(velocity) + self.kg * angle.cos() + self.kv * velocity + self.ka * acceleration
+  }
+
+  pub fn max_achievable_velocity(&self, max_voltage: f64, angle: f64, acceleration: f64) -> f64 {
+    (max_voltage - self.ks - self.kg * angle.cos() - self.ka * acceleration) / self.kv
+  }
+
+  pub fn min_achievable_velocity(&self, max_voltage: f64, angle: f64, acceleration: f64) -> f64 {
+    (-max_voltage + self.ks - self.kg * angle.cos() - self.ka * acceleration) / self.kv
+  }
+
+  pub fn max_achievable_acceleration(&self, max_voltage: f64, angle: f64, velocity: f64) -> f64 {
+    (max_voltage - self.ks * sign(velocity) - self.kg * angle.cos() - self.kv * velocity) / self.ka
+  }
+
+  pub fn min_achievable_acceleration(&self, max_voltage: f64, angle: f64, velocity: f64) -> f64 {
+    self.max_achievable_acceleration(-max_voltage, angle, velocity)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use std::f64::consts::PI;
+
+  use approx::assert_relative_eq;
+
+  use crate::models::DcMotor;
+
+  use super::{SimpleMotorFeedforward, ElevatorFeedforward, ArmFeedforward};
+
+  #[test]
+  fn test_simple() {
+    let ff = SimpleMotorFeedforward::new(0.5, 2.0, 0.1);
+    assert_relative_eq!(ff.calculate(0.0, 0.0), 0.0);
+    assert_relative_eq!(ff.calculate(2.0, 1.0), 4.6);
+    assert_relative_eq!(ff.calculate(-2.0, 0.0), -4.5);
+
+    let v = ff.max_achievable_velocity(12.0, 5.0);
+    assert_relative_eq!(ff.calculate(v, 5.0), 12.0);
+    let a = ff.min_achievable_acceleration(12.0, 3.0);
+    assert_relative_eq!(ff.calculate(3.0, a), -12.0);
+  }
+
+  #[test]
+  fn test_elevator() {
+    let motor = DcMotor::neo().reduce(20.0);
+    let ff = ElevatorFeedforward::from_motor(&motor, 10.0, 0.05);
+    assert_relative_eq!(ff.calculate(0.0, 0.0), motor.voltage(10.0 * 9.81 * 0.05, 0.0));
+    // DcMotor works in RPM
+    let rpm = 0.5 / 0.05 * 60.0 / (2.0 * PI);
+    assert_relative_eq!(ff.calculate(0.5, 2.0), motor.voltage(10.0 * (9.81 + 2.0) * 0.05, rpm), epsilon = 1e-9);
+
+    let a = ff.max_achievable_acceleration(12.0, 0.5);
+    assert_relative_eq!(ff.calculate(0.5, a), 12.0);
+  }
+
+  #[test]
+  fn test_kv() {
+    // NEO: R = 12 / 105 ohms, kw = 5676 RPM / (12 - 1.8 R) V = 481.25 RPM/V = 50.397 rad/s/V
+    let ff = SimpleMotorFeedforward::from_motor(&DcMotor::neo(), 0.01);
+    assert_relative_eq!(ff.kv, 0.019843, epsilon = 1e-6);
+
+    // Through a 20:1 reduction and 5cm spool, 1 m/s is 400 rad/s at the motor
+    let ff = ElevatorFeedforward::from_motor(&DcMotor::neo().reduce(20.0), 10.0, 0.05);
+    assert_relative_eq!(ff.kv, 7.9370, epsilon = 1e-4);
+  }
+
+  #[test]
+  fn test_arm() {
+    let ff = ArmFeedforward::new(0.0, 1.0, 2.0, 0.5);
+    assert_relative_eq!(ff.calculate(0.0, 0.0, 0.0), 1.0);
+    assert_relative_eq!(ff.calculate(PI / 2.0, 1.0, 0.0), 2.0, epsilon = 1e-9);
+    let v = ff.max_achievable_velocity(12.0, PI / 3.0, 0.0);
+    assert_relative_eq!(ff.calculate(PI / 3.0, v, 0.0), 12.0);
+  }
+}
@@ -0,0 +1,107 @@
+use nalgebra::SVector;
+
+use crate::{models::LinearSystem, estimation::KalmanFilter, actuators::motors::MotorController};
+
+use super::lqr::LinearQuadraticRegulator;
+
+/// Combines a plant, LQR controller and Kalman filter into a single control loop. Each loop iteration:
+///   1. correct() with the latest sensor measurements
+///   2. set_next_r() with the reference (goal) state
+///   3. predict() to calculate the (clamped) input, and project the state estimate forwards
+///   4. Apply u() to the motors, e.g. with set_motor()
+pub struct LinearSystemLoop<const S: usize, const I: usize, const O: usize> {
+  controller: LinearQuadraticRegulator<S, I>,
+  observer: KalmanFilter<S, I, O>,
+  max_input: SVector<f64, I>,
+  next_r: SVector<f64, S>,
+  u: SVector<f64, I>
+}
+
+impl<const S: usize, const I: usize, const O: usize> LinearSystemLoop<S, I, O> {
+  /// Create a loop, clamping each input to +/- the given maximum (e.g. 12V)
+  pub fn new(controller: LinearQuadraticRegulator<S, I>, observer: KalmanFilter<S, I, O>, max_input: f64) -> Self {
+    Self {
+      controller, observer,
+      max_input: SVector::repeat(max_input),
+      next_r: SVector::zeros(),
+      u: SVector::zeros()
+    }
+  }
+
+  /// Create a loop for a system, from Bryson's rule tolerances (see LinearQuadraticRegulator) and noise standard
+  /// deviations (see KalmanFilter).
+  pub fn from_system(
+    system: LinearSystem<S, I, O>, state_tolerances: [f64; S], input_tolerances: [f64; I],
+    state_std_devs: [f64; S], measurement_std_devs: [f64; O], max_input: f64, dt: f64
+  ) -> anyhow::Result<Self> {
+    let controller = LinearQuadraticRegulator::new(&system, state_tolerances, input_tolerances, dt)?;
+    let observer = KalmanFilter::new(system, state_std_devs, measurement_std_devs, dt)?;
+    Ok(Self::new(controller, observer, max_input))
+  }
+
+  pub fn controller(&self) -> &LinearQuadraticRegulator<S, I> { &self.controller }
+  pub fn controller_mut(&mut self) -> &mut LinearQuadraticRegulator<S, I> { &mut self.controller }
+  pub fn observer(&self) -> &KalmanFilter<S, I, O> { &self.observer }
+  pub fn observer_mut(&mut self) -> &mut KalmanFilter<S, I, O> { &mut self.observer }
+
+  pub fn x_hat(&self) -> &SVector<f64, S> { self.observer.x_hat() }
+
+  pub fn next_r(&self) -> &SVector<f64, S> { &self.next_r }
+
+  pub fn set_next_r(&mut self, r: SVector<f64, S>) {
+    self.next_r = r;
+  }
+
+  /// The clamped input, as of the last call to predict
+  pub fn u(&self) -> &SVector<f64, I> { &self.u }
+
+  /// Reset the state estimate and controller, e.g. when the robot is enabled
+  pub fn reset(&mut self, initial_state: SVector<f64, S>) {
+    self.controller.reset();
+    self.observer.set_x_hat(initial_state);
+    self.next_r = initial_state;
+    self.u = SVector::zeros();
+  }
+
+  pub fn correct(&mut self, y: &SVector<f64, O>) {
+    self.observer.correct(&self.u, y);
+  }
+
+  pub fn predict(&mut self, dt: f64) {
+    let u = self.controller.calculate(self.observer.x_hat(), &self.next_r);
+    self.u = u.zip_map(&self.max_input, |u, max| u.clamp(-max, max));
+    self.observer.predict(&self.u, dt);
+  }
+
+  /// Apply one of the inputs as a voltage to a motor
+  pub fn set_motor<M: MotorController>(&self, index: usize, motor: &mut M) {
+    motor.set_voltage(self.u[index]);
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use approx::assert_relative_eq;
+  use nalgebra::{Vector1, Vector2};
+
+  use crate::models::{plants::elevator, DcMotor};
+
+  use super::LinearSystemLoop;
+
+  #[test]
+  fn test_loop() {
+    let sys = elevator(&DcMotor::neo(), 5.0, 0.02, 10.0);
+    let mut lsl = LinearSystemLoop::from_system(sys.clone(), [0.02, 0.4], [12.0], [0.05, 1.0], [0.001], 12.0, 0.02).unwrap();
+
+    let mut x = Vector2::zeros();
+    lsl.reset(x);
+    lsl.set_next_r(Vector2::new(1.0, 0.0));
+    for _ in 0..300 {
+      lsl.correct(&Vector1::new(x[0]));
+      lsl.predict(0.02);
+      assert!(lsl.u()[0].abs() <= 12.0);
+      x = sys.calculate_x(&x, lsl.u(), 0.02);
+    }
+    assert_relative_eq!(x[0], 1.0, epsilon = 1e-3);
+  }
+}
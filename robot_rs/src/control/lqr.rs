@@ -0,0 +1,135 @@
+use anyhow::anyhow;
+use nalgebra::{SMatrix, SVector};
+
+use crate::models::LinearSystem;
+
+/// Solve the discrete algebraic Riccati equation
+///   X = A^T X A - A^T X B (R + B^T X B)^-1 B^T X A + Q
+/// using the structure-preserving doubling algorithm. Returns None if R isn't invertible or the solution
+/// doesn't converge (e.g. if (A, B) isn't stabilisable).
+pub fn dare<const S: usize, const I: usize>(a: &SMatrix<f64, S, S>, b: &SMatrix<f64, S, I>, q: &SMatrix<f64, S, S>, r: &SMatrix<f64, I, I>) -> Option<SMatrix<f64, S, S>> {
+  let mut a_k = *a;
+  let mut g_k = b * r.try_inverse()? * b.transpose();
+  let mut h_k = *q;
+
+  for _ in 0..100 {
+    let w = (SMatrix::<f64, S, S>::identity() + g_k * h_k).try_inverse()?;
+    let v = w * a_k;
+
+    let a_next = a_k * v;
+    let g_next = g_k + a_k * w * g_k * a_k.transpose();
+    let h_next = h_k + a_k.transpose() * h_k * v;
+
+    if !h_next.iter().all(|x| x.is_finite()) {
+      return None
+    }
+    if (h_next - h_k).norm() <= 1e-10 * h_next.norm() {
+      return Some(h_next)
+    }
+
+    (a_k, g_k, h_k) = (a_next, g_next, h_next);
+  }
+
+  None
+}
+
+/// Build a cost matrix using Bryson's rule, where each element is the maximum acceptable excursion of that
+/// state (or input). Infinite tolerances are given no cost.
+pub fn bryson<const N: usize>(tolerances: [f64; N]) -> SMatrix<f64, N, N> {
+  SMatrix::from_diagonal(&SVector::from_fn(|i, _| {
+    let tol = tolerances[i];
+    if tol.is_infinite() { 0.0 } else { 1.0 / (tol * tol) }
+  }))
+}
+
+/// A linear-quadratic regulator, which finds the optimal gain K for the control law u = K(r - x), trading off
+/// state error (Q) against control effort (R).
+#[derive(Debug, Clone)]
+pub struct LinearQuadraticRegulator<const S: usize, const I: usize> {
+  k: SMatrix<f64, I, S>,
+  r: SVector<f64, S>,
+  u: SVector<f64, I>
+}
+
+impl<const S: usize, const I: usize> LinearQuadraticRegulator<S, I> {
+  /// Create a regulator for a system, with Bryson's rule weights: the maximum acceptable error in each state, and
+  /// the maximum acceptable effort (e.g. voltage) for each input. `dt` is the loop period, in seconds.
+  pub fn new<const O: usize>(system: &LinearSystem<S, I, O>, state_tolerances: [f64; S], input_tolerances: [f64; I], dt: f64) -> anyhow::Result<Self> {
+    let (a, b) = system.discretize(dt);
+    Self::from_matrices(&a, &b, &bryson(state_tolerances), &bryson(input_tolerances))
+  }
+
+  /// Create a regulator from the discrete system matrices and cost matrices
+  pub fn from_matrices(a: &SMatrix<f64, S, S>, b: &SMatrix<f64, S, I>, q: &SMatrix<f64, S, S>, r: &SMatrix<f64, I, I>) -> anyhow::Result<Self> {
+    let x = dare(a, b, q, r).ok_or_else(|| anyhow!("Could not solve the DARE. Is the system stabilisable?"))?;
+    let k = (r + b.transpose() * x * b).try_inverse().ok_or_else(|| anyhow!("R + B^T X B is not invertible"))? * b.transpose() * x * a;
+    Ok(Self::from_gain(k))
+  }
+
+  pub fn from_gain(k: SMatrix<f64, I, S>) -> Self {
+    Self { k, r: SVector::zeros(), u: SVector::zeros() }
+  }
+
+  /// Adjust the gain to compensate for a delay between measuring the state and applying the input (e.g. CAN latency),
+  /// by projecting the state forwards by the delay under the closed-loop dynamics.
+  pub fn latency_compensate<const O: usize>(&mut self, system: &LinearSystem<S, I, O>, input_delay: f64) {
+    let (a, b) = system.discretize(input_delay);
+    self.k = self.k * (a - b * self.k);
+  }
+
+  pub fn k(&self) -> &SMatrix<f64, I, S> { &self.k }
+
+  pub fn r(&self) -> &SVector<f64, S> { &self.r }
+
+  pub fn u(&self) -> &SVector<f64, I> { &self.u }
+
+  pub fn reset(&mut self) {
+    self.r = SVector::zeros();
+    self.u = SVector::zeros();
+  }
+
+  pub fn calculate(&mut self, x: &SVector<f64, S>, r: &SVector<f64, S>) -> SVector<f64, I> {
+    self.r = *r;
+    self.u = self.k * (r - x);
+    self.u
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use approx::assert_relative_eq;
+  use nalgebra::{Matrix1, Vector2};
+
+  use crate::models::{plants::elevator, DcMotor};
+
+  use super::{dare, LinearQuadraticRegulator};
+
+  #[test]
+  fn test_dare_scalar() {
+    // X = X - X^2 / (1 + X) + 1, so X^2 - X - 1 = 0
+    let x = dare(&Matrix1::new(1.0), &Matrix1::new(1.0), &Matrix1::new(1.0), &Matrix1::new(1.0)).unwrap();
+    assert_relative_eq!(x[(0, 0)], (1.0 + 5.0f64.sqrt()) / 2.0, epsilon = 1e-9);
+
+    let lqr = LinearQuadraticRegulator::from_matrices(&Matrix1::new(1.0), &Matrix1::new(1.0), &Matrix1::new(1.0), &Matrix1::new(1.0)).unwrap();
+    assert_relative_eq!(lqr.k()[(0, 0)], (5.0f64.sqrt() - 1.0) / 2.0, epsilon = 1e-9);
+  }
+
+  #[test]
+  fn test_lqr_elevator() {
+    let sys = elevator(&DcMotor::neo(), 5.0, 0.02, 10.0);
+    let mut lqr = LinearQuadraticRegulator::new(&sys, [0.02, 0.4], [12.0], 0.02).unwrap();
+
+    let mut x = Vector2::zeros();
+    for _ in 0..250 {
+      let u = lqr.calculate(&x, &Vector2::new(1.0, 0.0)).map(|v| v.clamp(-12.0, 12.0));
+      x = sys.calculate_x(&x, &u, 0.02);
+    }
+    assert_relative_eq!(x[0], 1.0, epsilon = 1e-3);
+    assert!(lqr.calculate(&x, &Vector2::new(1.0, 0.0))[0].abs() < 0.1);
+
+    let k = lqr.k()[(0, 0)];
+    lqr.latency_compensate(&sys, 0.01);
+    assert!(lqr.k()[(0, 0)] < k);
+    assert_eq!(lqr.calculate(&x, &Vector2::new(1.0, 0.0)), lqr.k() * (Vector2::new(1.0, 0.0) - x));
+  }
+}
@@ -1,3 +1,10 @@
+pub mod bang_bang;
 pub mod control_lock;
 pub mod edge_detect;
-pub mod pid;
\ No newline at end of file
+pub mod feedforward;
+pub mod linear_system_loop;
+pub mod lqr;
+pub mod pid;
+pub mod profile;
+pub mod profiled_pid;
+pub mod slew_rate;
\ No newline at end of file
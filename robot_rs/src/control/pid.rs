@@ -2,12 +2,29 @@ use std::{collections::VecDeque, fmt::Debug};
 
 use nt4_rs::{nt, types::Value};
 
-#[derive(Clone, Debug)]
+use crate::types::MinMax;
+
+#[derive(Clone, Debug, Default)]
 pub struct PIDConfig {
   pub kp: f64,
   pub ki: f64,
   pub kd: f64,
+  /// Only accumulate the integral while the error is within +/- izone. The integral is reset outside of the zone.
   pub izone: Option<f64>,
+  /// Limit the magnitude of the integral term (ki * integral_sum)
+  pub integral_limit: Option<f64>,
+  /// Clamp the output to this range. The integral won't accumulate while the output is saturated (anti-windup).
+  pub output_limits: Option<MinMax<f64>>,
+  /// Treat the input as continuous over this range (e.g. -PI to PI for an angle), so the error takes the shortest path.
+  pub continuous_input: Option<MinMax<f64>>,
+  /// Calculate the derivative from the process variable instead of the error, so changes in setpoint don't cause
+  /// a spike in the output ("derivative kick").
+  pub derivative_on_measurement: bool,
+  /// Time constant (in seconds) of a low-pass filter on the derivative, to reduce noise. Larger values filter more.
+  pub derivative_filter: Option<f64>,
+  /// Use a fixed period (in seconds) between calls to calculate instead of the difference in time, so the controller
+  /// behaves the same regardless of timing jitter (e.g. in tests and on the robot).
+  pub period: Option<f64>,
 }
 
 #[derive(Default, Debug, Clone)]
@@ -67,23 +84,74 @@ pub fn new(config: PIDConfig, setpoint: f64, history: f64) -> Self {
     self.setpoint
   }
 
+  /// Wrap a difference in the input into the continuous input range, if there is one
+  fn wrap(&self, difference: f64) -> f64 {
+    match &self.config.continuous_input {
+      Some(range) => {
+        let span = range.max - range.min;
+        (difference + span / 2.0).rem_euclid(span) - span / 2.0
+      },
+      None => difference
+    }
+  }
+
+  fn clamp_output(&self, output: f64) -> f64 {
+    match &self.config.output_limits {
+      Some(limits) => output.clamp(limits.min, limits.max),
+      None => output
+    }
+  }
+
   pub fn calculate(&mut self, pv: f64, time: f64) -> &PIDMeasurement {
     let last = self.last();
-    let error = self.setpoint - pv;
+    let error = self.wrap(self.setpoint - pv);
 
     let measurement: PIDMeasurement = match last {
       Some(last) => {
-        let dt = time - last.time;
-        let derivative = (error - last.error) / dt;
-        let integral = match self.config.izone {
-          Some(izone) if error < izone && error > -izone => 0.0,
-          Some(_) | None => error * dt,
+        let dt = self.config.period.unwrap_or(time - last.time);
+
+        // Duplicated or out-of-order timestamps would blow up the derivative, so hold the derivative and integral
+        // until time moves forwards again.
+        let (derivative, mut integral_sum) = if dt > 0.0 {
+          let raw_derivative = if self.config.derivative_on_measurement {
+            -self.wrap(pv - last.process_variable) / dt
+          } else {
+            (error - last.error) / dt
+          };
+
+          let derivative = match self.config.derivative_filter {
+            Some(tau) => last.derivative + dt / (tau + dt) * (raw_derivative - last.derivative),
+            None => raw_derivative
+          };
+
+          let integral_sum = match self.config.izone {
+            Some(izone) if error.abs() > izone => 0.0,
+            _ => last.integral_sum + error * dt,
+          };
+
+          (derivative, integral_sum)
+        } else {
+          (last.derivative, last.integral_sum)
         };
 
-        let integral_sum = last.integral_sum + integral;
+        if let Some(limit) = self.config.integral_limit {
+          if self.config.ki != 0.0 {
+            let max_sum = (limit / self.config.ki).abs();
+            integral_sum = integral_sum.clamp(-max_sum, max_sum);
+          }
+        }
 
-        let parts = [ self.config.kp * error, self.config.ki * integral_sum, self.config.kd * derivative ];
-        let output = parts.iter().sum();
+        let p = self.config.kp * error;
+        let d = self.config.kd * derivative;
+        let unclamped = p + self.config.ki * integral_sum + d;
+
+        // Anti-windup: if the output is saturated, don't let the integral grow any further in the direction of saturation
+        if self.clamp_output(unclamped) != unclamped && integral_sum.abs() > last.integral_sum.abs() && self.config.ki * integral_sum * unclamped > 0.0 {
+          integral_sum = last.integral_sum;
+        }
+
+        let parts = [ p, self.config.ki * integral_sum, d ];
+        let output = self.clamp_output(parts.iter().sum());
         PIDMeasurement {
           time,
           setpoint: self.setpoint,
@@ -103,7 +171,7 @@ pub fn new(config: PIDConfig, setpoint: f64, history: f64) -> Self {
           error,
           derivative: 0.0,
           integral_sum: 0.0,
-          output: self.config.kp * error,
+          output: self.clamp_output(self.config.kp * error),
           output_parts: [self.config.kp * error, 0.0, 0.0]
         }
       }
@@ -167,3 +235,90 @@ pub fn new(config: PIDConfig, setpoint: f64, history: f64) -> Self {
     nt!(&format!("{}/{}", basepath, "output_parts"), last.output_parts.to_vec()).unwrap();
   }
 }
+
+#[cfg(test)]
+mod test {
+  use std::f64::consts::PI;
+
+  use approx::assert_relative_eq;
+
+  use crate::types::MinMax;
+
+  use super::{PID, PIDConfig};
+
+  #[test]
+  fn test_izone() {
+    let mut pid = PID::new(PIDConfig { ki: 1.0, izone: Some(0.5), ..Default::default() }, 1.0, 1.0);
+    pid.calculate(0.0, 0.0);
+    assert_relative_eq!(pid.calculate(0.0, 1.0).integral_sum, 0.0);
+    assert_relative_eq!(pid.calculate(0.75, 2.0).integral_sum, 0.25);
+    assert_relative_eq!(pid.calculate(0.75, 3.0).integral_sum, 0.5);
+    assert_relative_eq!(pid.calculate(0.0, 4.0).integral_sum, 0.0);
+  }
+
+  #[test]
+  fn test_output_limits() {
+    let config = PIDConfig { kp: 1.0, ki: 1.0, output_limits: Some(MinMax::new(-1.0, 1.0)), ..Default::default() };
+    let mut pid = PID::new(config, 5.0, 1.0);
+    assert_relative_eq!(pid.calculate(0.0, 0.0).output, 1.0);
+    for i in 1..10 {
+      assert_relative_eq!(pid.calculate(0.0, i as f64).output, 1.0);
+    }
+    // The integral hasn't wound up while saturated, so the output responds straight away
+    assert_relative_eq!(pid.last().unwrap().integral_sum, 0.0);
+    assert_relative_eq!(pid.calculate(5.5, 10.0).output, -1.0);
+  }
+
+  #[test]
+  fn test_integral_limit() {
+    let mut pid = PID::new(PIDConfig { ki: 2.0, integral_limit: Some(1.0), ..Default::default() }, 1.0, 1.0);
+    pid.calculate(0.0, 0.0);
+    pid.calculate(0.0, 1.0);
+    assert_relative_eq!(pid.calculate(0.0, 2.0).output, 1.0);
+  }
+
+  #[test]
+  fn test_continuous() {
+    let config = PIDConfig { kp: 1.0, continuous_input: Some(MinMax::new(-PI, PI)), ..Default::default() };
+    let mut pid = PID::new(config, PI - 0.1, 1.0);
+    assert_relative_eq!(pid.calculate(-PI + 0.1, 0.0).error, -0.2, epsilon = 1e-9);
+  }
+
+  #[test]
+  fn test_dt_guard() {
+    let mut pid = PID::new(PIDConfig { ki: 1.0, kd: 1.0, ..Default::default() }, 1.0, 1.0);
+    pid.calculate(0.0, 0.0);
+    let last = pid.calculate(0.5, 0.5).clone();
+    let dup = pid.calculate(0.25, 0.5);
+    assert!(dup.output.is_finite());
+    assert_relative_eq!(dup.derivative, last.derivative);
+    assert_relative_eq!(dup.integral_sum, last.integral_sum);
+    assert!(pid.calculate(0.25, 0.4).output.is_finite());
+  }
+
+  #[test]
+  fn test_derivative_filter() {
+    let mut pid = PID::new(PIDConfig { kd: 1.0, derivative_filter: Some(0.3), period: Some(0.1), ..Default::default() }, 0.0, 1.0);
+    pid.calculate(0.0, 0.0);
+    // Raw derivative is -10, filtered with alpha = 0.1 / (0.3 + 0.1) = 0.25
+    assert_relative_eq!(pid.calculate(1.0, 0.1).derivative, -2.5);
+    assert_relative_eq!(pid.calculate(1.0, 0.2).derivative, -1.875);
+  }
+
+  #[test]
+  fn test_fixed_period() {
+    let mut pid = PID::new(PIDConfig { ki: 1.0, period: Some(0.02), ..Default::default() }, 1.0, 1.0);
+    pid.calculate(0.0, 0.0);
+    assert_relative_eq!(pid.calculate(0.0, 0.5).integral_sum, 0.02);
+    assert_relative_eq!(pid.calculate(0.0, 0.5).integral_sum, 0.04);
+  }
+
+  #[test]
+  fn test_derivative_on_measurement() {
+    let mut pid = PID::new(PIDConfig { kd: 1.0, derivative_on_measurement: true, ..Default::default() }, 0.0, 1.0);
+    pid.calculate(0.0, 0.0);
+    pid.set_setpoint(1.0);
+    assert_relative_eq!(pid.calculate(0.0, 0.1).output, 0.0);
+    assert_relative_eq!(pid.calculate(0.1, 0.2).output, -1.0);
+  }
+}
@@ -0,0 +1,46 @@
+pub mod scurve;
+pub mod trapezoid;
+
+pub use scurve::*;
+pub use trapezoid::*;
+
+/// A point along a motion profile. Acceleration is given for use with feedforward, and is ignored when
+/// used as the initial or goal state of a profile.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ProfileState {
+  pub position: f64,
+  pub velocity: f64,
+  pub acceleration: f64
+}
+
+impl ProfileState {
+  pub fn new(position: f64, velocity: f64) -> Self {
+    Self { position, velocity, acceleration: 0.0 }
+  }
+
+  pub fn at_rest(position: f64) -> Self {
+    Self::new(position, 0.0)
+  }
+}
+
+/// A motion profile, planned from an initial state to a goal state. Time is given in seconds since the
+/// start of the profile. To re-plan mid-motion (e.g. when the goal changes), plan a new profile starting
+/// from the current state of the old one.
+pub trait MotionProfile {
+  type Constraints: Clone;
+
+  fn plan(constraints: Self::Constraints, initial: ProfileState, goal: ProfileState) -> Self where Self: Sized;
+
+  fn state_at(&self, time: f64) -> ProfileState;
+
+  /// Total duration of the profile
+  fn total_time(&self) -> f64;
+
+  fn time_left(&self, time: f64) -> f64 {
+    (self.total_time() - time).max(0.0)
+  }
+
+  fn is_finished(&self, time: f64) -> bool {
+    time >= self.total_time()
+  }
+}
@@ -0,0 +1,235 @@
+use super::{MotionProfile, ProfileState};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SCurveConstraints {
+  pub max_velocity: f64,
+  pub max_acceleration: f64,
+  pub max_jerk: f64
+}
+
+impl SCurveConstraints {
+  pub fn new(max_velocity: f64, max_acceleration: f64, max_jerk: f64) -> Self {
+    Self { max_velocity, max_acceleration, max_jerk }
+  }
+}
+
+/// A jerk-limited change in velocity, starting and ending with zero acceleration. Made up of three segments:
+/// ramp acceleration up, hold, and ramp back down. The hold is skipped if the change is too small to reach
+/// the maximum acceleration.
+#[derive(Debug, Clone, Copy)]
+struct VelocityChange {
+  from: f64,
+  to: f64,
+  jerk: f64,
+  peak_acceleration: f64,
+  jerk_time: f64,
+  hold_time: f64
+}
+
+impl VelocityChange {
+  fn new(from: f64, to: f64, max_acceleration: f64, max_jerk: f64) -> Self {
+    let dv = (to - from).abs();
+    let sign = if to < from { -1.0 } else { 1.0 };
+
+    let (jerk_time, hold_time) = if dv >= max_acceleration * max_acceleration / max_jerk {
+      (max_acceleration / max_jerk, dv / max_acceleration - max_acceleration / max_jerk)
+    } else {
+      ((dv / max_jerk).sqrt(), 0.0)
+    };
+
+    Self {
+      from, to,
+      jerk: sign * max_jerk,
+      peak_acceleration: sign * max_jerk * jerk_time,
+      jerk_time, hold_time
+    }
+  }
+
+  fn duration(&self) -> f64 {
+    2.0 * self.jerk_time + self.hold_time
+  }
+
+  fn distance(&self) -> f64 {
+    // The acceleration curve is symmetric, so the average velocity is halfway between the two
+    (self.from + self.to) / 2.0 * self.duration()
+  }
+
+  /// State relative to the start of the change (position starts at 0)
+  fn state_at(&self, t: f64) -> ProfileState {
+    let (j, a_peak, tj) = (self.jerk, self.peak_acceleration, self.jerk_time);
+
+    // State at the end of the ramp up, and at the end of the hold
+    let v1 = self.from + j * tj * tj / 2.0;
+    let p1 = self.from * tj + j * tj * tj * tj / 6.0;
+    let v2 = v1 + a_peak * self.hold_time;
+    let p2 = p1 + v1 * self.hold_time + a_peak * self.hold_time * self.hold_time / 2.0;
+
+    if t < tj {
+      ProfileState { position: self.from * t + j * t * t * t / 6.0, velocity: self.from + j * t * t / 2.0, acceleration: j * t }
+    } else if t < tj + self.hold_time {
+      let t = t - tj;
+      ProfileState { position: p1 + v1 * t + a_peak * t * t / 2.0, velocity: v1 + a_peak * t, acceleration: a_peak }
+    } else if t < self.duration() {
+      let t = t - tj - self.hold_time;
+      ProfileState {
+        position: p2 + v2 * t + a_peak * t * t / 2.0 - j * t * t * t / 6.0,
+        velocity: v2 + a_peak * t - j * t * t / 2.0,
+        acceleration: a_peak - j * t
+      }
+    } else {
+      ProfileState::new(self.distance(), self.to)
+    }
+  }
+}
+
+/// A jerk-limited ("S-curve") motion profile, made up of seven segments: ramp up acceleration, hold acceleration,
+/// ramp down acceleration, cruise, and the same again in reverse to decelerate. Smoother than a trapezoid profile,
+/// at the cost of taking slightly longer.
+///
+/// The initial and goal accelerations are assumed to be zero. If the goal can't be reached without overshooting
+/// (e.g. the initial velocity is too high to stop in time), the profile will overshoot and then jump to the goal
+/// once it's finished.
+#[derive(Debug, Clone)]
+pub struct SCurveProfile {
+  constraints: SCurveConstraints,
+  // Direction of travel. The profile is planned as if travelling forwards, and flipped back on output.
+  direction: f64,
+  initial: ProfileState,
+  goal: ProfileState,
+  accel: VelocityChange,
+  cruise_velocity: f64,
+  cruise_time: f64,
+  decel: VelocityChange
+}
+
+impl SCurveProfile {
+  pub fn new(constraints: SCurveConstraints, initial: ProfileState, goal: ProfileState) -> Self {
+    let direction = if goal.position < initial.position { -1.0 } else { 1.0 };
+    let max_v = constraints.max_velocity;
+    let flip = |s: ProfileState| ProfileState::new(s.position * direction, (s.velocity * direction).clamp(-max_v, max_v));
+    let (initial, goal) = (flip(initial), flip(goal));
+
+    let distance = goal.position - initial.position;
+    let changes = |peak: f64| (
+      VelocityChange::new(initial.velocity, peak, constraints.max_acceleration, constraints.max_jerk),
+      VelocityChange::new(peak, goal.velocity, constraints.max_acceleration, constraints.max_jerk)
+    );
+    let distance_at = |peak: f64| {
+      let (a, b) = changes(peak);
+      a.distance() + b.distance()
+    };
+
+    // Find the highest peak velocity that doesn't overshoot the goal. The distance covered is increasing
+    // with the peak velocity, so we can bisect to find it if we can't reach the max velocity.
+    let peak = if distance_at(max_v) <= distance {
+      max_v
+    } else {
+      let (mut low, mut high) = (initial.velocity.max(goal.velocity), max_v);
+      for _ in 0..60 {
+        let mid = (low + high) / 2.0;
+        if distance_at(mid) <= distance { low = mid } else { high = mid }
+      }
+      low
+    };
+
+    let (accel, decel) = changes(peak);
+    let cruise_time = if peak > 0.0 { ((distance - accel.distance() - decel.distance()) / peak).max(0.0) } else { 0.0 };
+
+    Self { constraints, direction, initial, goal, accel, cruise_velocity: peak, cruise_time, decel }
+  }
+
+  pub fn constraints(&self) -> &SCurveConstraints { &self.constraints }
+
+  fn flipped_state_at(&self, t: f64) -> ProfileState {
+    let end_accel = self.accel.duration();
+    let end_cruise = end_accel + self.cruise_time;
+
+    let relative = if t < 0.0 {
+      return ProfileState { acceleration: 0.0, ..self.initial }
+    } else if t < end_accel {
+      self.accel.state_at(t)
+    } else if t < end_cruise {
+      ProfileState::new(self.accel.distance() + self.cruise_velocity * (t - end_accel), self.cruise_velocity)
+    } else if t < self.total_time() {
+      let state = self.decel.state_at(t - end_cruise);
+      ProfileState { position: state.position + self.accel.distance() + self.cruise_velocity * self.cruise_time, ..state }
+    } else {
+      return self.goal
+    };
+
+    ProfileState { position: relative.position + self.initial.position, ..relative }
+  }
+}
+
+impl MotionProfile for SCurveProfile {
+  type Constraints = SCurveConstraints;
+
+  fn plan(constraints: SCurveConstraints, initial: ProfileState, goal: ProfileState) -> Self {
+    Self::new(constraints, initial, goal)
+  }
+
+  fn state_at(&self, time: f64) -> ProfileState {
+    let state = self.flipped_state_at(time);
+    ProfileState {
+      position: state.position * self.direction,
+      velocity: state.velocity * self.direction,
+      acceleration: state.acceleration * self.direction
+    }
+  }
+
+  fn total_time(&self) -> f64 {
+    self.accel.duration() + self.cruise_time + self.decel.duration()
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use approx::assert_relative_eq;
+
+  use crate::control::profile::{MotionProfile, ProfileState};
+
+  use super::{SCurveProfile, SCurveConstraints};
+
+  #[test]
+  fn test_scurve() {
+    let profile = SCurveProfile::new(SCurveConstraints::new(1.0, 1.0, 1.0), ProfileState::at_rest(0.0), ProfileState::at_rest(10.0));
+    assert_relative_eq!(profile.total_time(), 12.0);
+    assert_relative_eq!(profile.state_at(1.0).position, 1.0 / 6.0);
+    assert_relative_eq!(profile.state_at(1.0).acceleration, 1.0);
+    assert_relative_eq!(profile.state_at(2.0).position, 1.0);
+    assert_relative_eq!(profile.state_at(2.0).velocity, 1.0);
+    assert_relative_eq!(profile.state_at(6.0).position, 5.0);
+    assert_relative_eq!(profile.state_at(11.0).position, 10.0 - 1.0 / 6.0);
+    assert_relative_eq!(profile.state_at(11.0).acceleration, -1.0);
+    assert_eq!(profile.state_at(12.5), ProfileState::at_rest(10.0));
+  }
+
+  #[test]
+  fn test_scurve_short() {
+    let constraints = SCurveConstraints::new(3.0, 2.0, 4.0);
+    let profile = SCurveProfile::new(constraints, ProfileState::at_rest(1.0), ProfileState::at_rest(-0.5));
+    let total = profile.total_time();
+    assert_relative_eq!(profile.state_at(total / 2.0).position, 0.25, epsilon = 1e-9);
+
+    // Check the constraints hold and the motion is continuous
+    let mut last = profile.state_at(0.0);
+    for i in 1..=1000 {
+      let state = profile.state_at(total * i as f64 / 1000.0);
+      assert!(state.velocity.abs() <= 3.0 + 1e-9);
+      assert!(state.acceleration.abs() <= 2.0 + 1e-9);
+      assert!(state.position <= last.position + 1e-12);
+      assert!((state.velocity - last.velocity).abs() < 0.01);
+      last = state;
+    }
+    assert_relative_eq!(last.position, -0.5);
+  }
+
+  #[test]
+  fn test_scurve_replan() {
+    let constraints = SCurveConstraints::new(1.0, 1.0, 1.0);
+    let profile = SCurveProfile::new(constraints, ProfileState::at_rest(0.0), ProfileState::at_rest(10.0));
+    let replanned = SCurveProfile::new(constraints, profile.state_at(4.0), ProfileState::at_rest(10.0));
+    assert_relative_eq!(replanned.total_time(), 8.0);
+    assert_relative_eq!(replanned.state_at(7.0).position, profile.state_at(11.0).position);
+  }
+}
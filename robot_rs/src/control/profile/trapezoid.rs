@@ -0,0 +1,184 @@
+use super::{MotionProfile, ProfileState};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrapezoidConstraints {
+  pub max_velocity: f64,
+  pub max_acceleration: f64
+}
+
+impl TrapezoidConstraints {
+  pub fn new(max_velocity: f64, max_acceleration: f64) -> Self {
+    Self { max_velocity, max_acceleration }
+  }
+}
+
+/// A trapezoidal motion profile: accelerate at the maximum acceleration, cruise at the maximum velocity, and
+/// decelerate into the goal. If the distance is too short to reach the maximum velocity, the cruise is skipped
+/// (a "triangular" profile). If the initial velocity is too high to stop before the goal, the profile decelerates
+/// past the goal and comes back to it.
+#[derive(Debug, Clone)]
+pub struct TrapezoidProfile {
+  constraints: TrapezoidConstraints,
+  // Direction of travel. The profile is planned as if travelling forwards, and flipped back on output.
+  direction: f64,
+  initial: ProfileState,
+  goal: ProfileState,
+  peak_velocity: f64,
+  end_accel: f64,
+  end_cruise: f64,
+  end_decel: f64
+}
+
+impl TrapezoidProfile {
+  pub fn new(constraints: TrapezoidConstraints, initial: ProfileState, goal: ProfileState) -> Self {
+    let (max_v, max_a) = (constraints.max_velocity, constraints.max_acceleration);
+    let clamp = |s: ProfileState| ProfileState::new(s.position, s.velocity.clamp(-max_v, max_v));
+    let (initial, goal) = (clamp(initial), clamp(goal));
+
+    // Going straight from the initial velocity to the goal velocity at the maximum acceleration covers this distance.
+    // If the goal is further than that we need to speed up (forwards) first, otherwise we need to slow down (backwards)
+    // first - which includes overshooting the goal and coming back if we're moving too fast to stop in time.
+    let direct_distance = (goal.velocity - initial.velocity).abs() * (goal.velocity + initial.velocity) / (2.0 * max_a);
+    let direction = if goal.position - initial.position < direct_distance { -1.0 } else { 1.0 };
+    let flip = |s: ProfileState| ProfileState::new(s.position * direction, s.velocity * direction);
+    let (initial, goal) = (flip(initial), flip(goal));
+
+    // Extend the profile backwards and forwards in time as if it started and ended at rest, which lets us handle
+    // initial and goal velocities as a regular trapezoid with the ends cut off.
+    let cutoff_begin = initial.velocity / max_a;
+    let cutoff_dist_begin = cutoff_begin * cutoff_begin * max_a / 2.0;
+    let cutoff_end = goal.velocity / max_a;
+    let cutoff_dist_end = cutoff_end * cutoff_end * max_a / 2.0;
+
+    let full_distance = cutoff_dist_begin + (goal.position - initial.position) + cutoff_dist_end;
+    let mut accel_time = max_v / max_a;
+    let mut cruise_distance = full_distance - accel_time * accel_time * max_a;
+
+    if cruise_distance < 0.0 {
+      accel_time = (full_distance / max_a).max(0.0).sqrt();
+      cruise_distance = 0.0;
+    }
+
+    let end_accel = (accel_time - cutoff_begin).max(0.0);
+    let end_cruise = end_accel + cruise_distance / max_v;
+    let end_decel = (end_cruise + accel_time - cutoff_end).max(end_cruise);
+
+    Self {
+      constraints, direction, initial, goal,
+      peak_velocity: initial.velocity + end_accel * max_a,
+      end_accel, end_cruise, end_decel
+    }
+  }
+
+  pub fn constraints(&self) -> &TrapezoidConstraints { &self.constraints }
+
+  fn flipped_state_at(&self, t: f64) -> ProfileState {
+    let a = self.constraints.max_acceleration;
+    let (p0, v0) = (self.initial.position, self.initial.velocity);
+
+    if t < 0.0 {
+      ProfileState { acceleration: 0.0, ..self.initial }
+    } else if t < self.end_accel {
+      ProfileState { position: p0 + (v0 + t * a / 2.0) * t, velocity: v0 + t * a, acceleration: a }
+    } else if t < self.end_cruise {
+      let accel_distance = (v0 + self.end_accel * a / 2.0) * self.end_accel;
+      ProfileState {
+        position: p0 + accel_distance + self.peak_velocity * (t - self.end_accel),
+        velocity: self.peak_velocity,
+        acceleration: 0.0
+      }
+    } else if t < self.end_decel {
+      let left = self.end_decel - t;
+      let (pf, vf) = (self.goal.position, self.goal.velocity);
+      ProfileState { position: pf - (vf + left * a / 2.0) * left, velocity: vf + left * a, acceleration: -a }
+    } else {
+      self.goal
+    }
+  }
+}
+
+impl MotionProfile for TrapezoidProfile {
+  type Constraints = TrapezoidConstraints;
+
+  fn plan(constraints: TrapezoidConstraints, initial: ProfileState, goal: ProfileState) -> Self {
+    Self::new(constraints, initial, goal)
+  }
+
+  fn state_at(&self, time: f64) -> ProfileState {
+    let state = self.flipped_state_at(time);
+    ProfileState {
+      position: state.position * self.direction,
+      velocity: state.velocity * self.direction,
+      acceleration: state.acceleration * self.direction
+    }
+  }
+
+  fn total_time(&self) -> f64 {
+    self.end_decel
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use approx::assert_relative_eq;
+
+  use crate::control::profile::{MotionProfile, ProfileState};
+
+  use super::{TrapezoidProfile, TrapezoidConstraints};
+
+  #[test]
+  fn test_trapezoid() {
+    let profile = TrapezoidProfile::new(TrapezoidConstraints::new(1.0, 1.0), ProfileState::at_rest(0.0), ProfileState::at_rest(2.0));
+    assert_relative_eq!(profile.total_time(), 3.0);
+    assert_relative_eq!(profile.state_at(0.5).position, 0.125);
+    assert_relative_eq!(profile.state_at(0.5).velocity, 0.5);
+    assert_relative_eq!(profile.state_at(1.5).position, 1.0);
+    assert_relative_eq!(profile.state_at(1.5).velocity, 1.0);
+    assert_relative_eq!(profile.state_at(2.5).position, 1.875);
+    assert_relative_eq!(profile.state_at(2.5).acceleration, -1.0);
+    assert_eq!(profile.state_at(4.0), ProfileState::at_rest(2.0));
+    assert_relative_eq!(profile.time_left(1.0), 2.0);
+  }
+
+  #[test]
+  fn test_triangle_reverse() {
+    let profile = TrapezoidProfile::new(TrapezoidConstraints::new(2.0, 1.0), ProfileState::at_rest(1.0), ProfileState::at_rest(0.0));
+    assert_relative_eq!(profile.total_time(), 2.0);
+    assert_relative_eq!(profile.state_at(1.0).position, 0.5);
+    assert_relative_eq!(profile.state_at(1.0).velocity, -1.0);
+    assert_relative_eq!(profile.state_at(0.5).acceleration, -1.0);
+  }
+
+  #[test]
+  fn test_replan() {
+    let constraints = TrapezoidConstraints::new(1.0, 1.0);
+    let profile = TrapezoidProfile::new(constraints, ProfileState::at_rest(0.0), ProfileState::at_rest(2.0));
+    let replanned = TrapezoidProfile::new(constraints, profile.state_at(1.5), ProfileState::at_rest(2.0));
+    assert_relative_eq!(replanned.total_time(), 1.5);
+    assert_relative_eq!(replanned.state_at(1.0).position, profile.state_at(2.5).position);
+    assert_relative_eq!(replanned.state_at(1.0).velocity, profile.state_at(2.5).velocity);
+  }
+
+  #[test]
+  fn test_too_fast_to_stop() {
+    // Stopping from 2m/s takes 2m, but the goal is only 1m away. Decelerate past the goal, then come back.
+    let initial = ProfileState::new(0.0, 2.0);
+    let goal = ProfileState::at_rest(1.0);
+    let profile = TrapezoidProfile::new(TrapezoidConstraints::new(3.0, 1.0), initial, goal);
+    assert_relative_eq!(profile.total_time(), 4.0);
+    assert_relative_eq!(profile.state_at(2.0).position, 2.0);
+    assert_relative_eq!(profile.state_at(2.0).velocity, 0.0);
+    assert_relative_eq!(profile.state_at(3.0).velocity, -1.0);
+    assert_relative_eq!(profile.state_at(3.999).position, 1.0, epsilon = 1e-3);
+
+    // No jumps in position or velocity along the way
+    let mut last = profile.state_at(0.0);
+    for i in 1..=400 {
+      let state = profile.state_at(i as f64 * 0.01);
+      assert!((state.position - last.position).abs() < 0.03);
+      assert!((state.velocity - last.velocity).abs() < 0.011);
+      last = state;
+    }
+    assert_eq!(last, goal);
+  }
+}
@@ -0,0 +1,168 @@
+use nt4_rs::nt;
+
+use super::{pid::{PID, PIDConfig, PIDMeasurement}, profile::{MotionProfile, ProfileState}};
+
+#[derive(Debug, Clone)]
+pub struct ProfiledPIDOutput {
+  /// Output of the PID loop. Add feedforward (from the setpoint's velocity and acceleration) to this.
+  pub output: f64,
+  /// Where the profile says we should be right now
+  pub setpoint: ProfileState
+}
+
+/// A PID controller whose goal is a position, but whose setpoint follows a motion profile towards that goal.
+/// This avoids the large initial error (and output) of jumping the setpoint straight to the goal.
+#[derive(Clone)]
+pub struct ProfiledPID<P: MotionProfile> {
+  pid: PID,
+  constraints: P::Constraints,
+  goal: ProfileState,
+  // The current profile, and the time it started
+  profile: Option<(P, f64)>,
+  replan: bool
+}
+
+impl<P: MotionProfile> ProfiledPID<P> {
+  pub fn new(config: PIDConfig, constraints: P::Constraints, history: f64) -> Self {
+    Self {
+      pid: PID::new(config, 0.0, history),
+      constraints,
+      goal: ProfileState::default(),
+      profile: None,
+      replan: true
+    }
+  }
+
+  pub fn pid(&self) -> &PID { &self.pid }
+
+  pub fn goal(&self) -> ProfileState { self.goal }
+
+  /// Set the goal. If it's changed, the profile is re-planned from the current setpoint on the next call to calculate.
+  pub fn set_goal(&mut self, goal: ProfileState) {
+    if goal != self.goal {
+      self.goal = goal;
+      self.replan = true;
+    }
+  }
+
+  pub fn set_constraints(&mut self, constraints: P::Constraints) {
+    self.constraints = constraints;
+    self.replan = true;
+  }
+
+  /// The current setpoint, as of the last call to calculate
+  pub fn setpoint(&self) -> Option<ProfileState> {
+    self.pid.last().and_then(|last| self.profile.as_ref().map(|(profile, start)| profile.state_at(last.time - start)))
+  }
+
+  pub fn last(&self) -> Option<&PIDMeasurement> {
+    self.pid.last()
+  }
+
+  /// Reset the controller, so the next profile starts from the measured position (at rest).
+  pub fn reset(&mut self) {
+    self.pid.reset();
+    self.profile = None;
+    self.replan = true;
+  }
+
+  pub fn calculate(&mut self, pv: f64, time: f64) -> ProfiledPIDOutput {
+    if self.replan || self.profile.is_none() {
+      let initial = match &self.profile {
+        Some((profile, start)) => profile.state_at(time - start),
+        None => ProfileState::at_rest(pv)
+      };
+      self.profile = Some((P::plan(self.constraints.clone(), initial, self.goal), time));
+      self.replan = false;
+    }
+
+    let (profile, start) = self.profile.as_ref().unwrap();
+    let setpoint = profile.state_at(time - start);
+
+    self.pid.set_setpoint(setpoint.position);
+    let output = self.pid.calculate(pv, time).output;
+    ProfiledPIDOutput { output, setpoint }
+  }
+
+  /// Time left until the profile reaches the goal
+  pub fn time_left(&self, time: f64) -> f64 {
+    self.profile.as_ref().map(|(profile, start)| profile.time_left(time - start)).unwrap_or(0.0)
+  }
+
+  /// Check if the controller has reached the goal, as in PID::is_stable. Unlike PID::is_stable, this is
+  /// relative to the goal and not the current setpoint, so it won't be stable while the profile is running.
+  pub fn is_stable(&self, error_threshold: f64, derivative_thresh: Option<f64>) -> bool {
+    let at_goal = match (&self.profile, self.pid.last()) {
+      (Some((profile, start)), Some(last)) => profile.is_finished(last.time - start),
+      _ => false
+    };
+    let goal_error = self.pid.get_setpoint() - self.goal.position;
+
+    at_goal && !self.replan && goal_error.abs() < error_threshold && self.pid.is_stable(error_threshold, derivative_thresh)
+  }
+
+  pub fn nt_update(&mut self, path: &str) {
+    self.pid.nt_update(path);
+
+    let basepath = path.to_owned() + "/profile";
+    let setpoint = self.setpoint().unwrap_or_default();
+    nt!(&format!("{}/{}", basepath, "goal"), self.goal.position).unwrap();
+    nt!(&format!("{}/{}", basepath, "position"), setpoint.position).unwrap();
+    nt!(&format!("{}/{}", basepath, "velocity"), setpoint.velocity).unwrap();
+    nt!(&format!("{}/{}", basepath, "acceleration"), setpoint.acceleration).unwrap();
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use approx::assert_relative_eq;
+
+  use crate::control::{pid::PIDConfig, profile::{TrapezoidProfile, TrapezoidConstraints, ProfileState}};
+
+  use super::ProfiledPID;
+
+  fn pid() -> ProfiledPID<TrapezoidProfile> {
+    ProfiledPID::new(PIDConfig { kp: 1.0, ..Default::default() }, TrapezoidConstraints::new(1.0, 1.0), 0.1)
+  }
+
+  #[test]
+  fn test_profiled_pid() {
+    let mut pid = pid();
+    pid.set_goal(ProfileState::at_rest(2.0));
+
+    let out = pid.calculate(0.0, 10.0);
+    assert_relative_eq!(out.setpoint.position, 0.0);
+    assert_relative_eq!(out.output, 0.0);
+
+    let out = pid.calculate(0.0, 10.5);
+    assert_relative_eq!(out.setpoint.position, 0.125);
+    assert_relative_eq!(out.setpoint.velocity, 0.5);
+    assert_relative_eq!(out.setpoint.acceleration, 1.0);
+    assert_relative_eq!(out.output, 0.125);
+    assert_relative_eq!(pid.time_left(10.5), 2.5);
+  }
+
+  #[test]
+  fn test_replan_and_stable() {
+    let mut pid = pid();
+    pid.set_goal(ProfileState::at_rest(2.0));
+    pid.calculate(0.0, 0.0);
+    pid.calculate(1.0, 1.5);
+
+    // Re-planning starts from the current setpoint, so there's no jump
+    pid.set_goal(ProfileState::at_rest(1.5));
+    let out = pid.calculate(1.0, 1.5);
+    assert_relative_eq!(out.setpoint.position, 1.0);
+    assert_relative_eq!(out.setpoint.velocity, 1.0);
+    assert!(!pid.is_stable(0.05, None));
+
+    for i in 0..100 {
+      let time = 2.0 + i as f64 * 0.05;
+      let setpoint = pid.calculate(pid.setpoint().unwrap().position, time).setpoint;
+      if time > 2.5 { assert_relative_eq!(setpoint.position, 1.5) }
+    }
+    let out = pid.calculate(1.5, 7.0);
+    assert_relative_eq!(out.output, 0.0);
+    assert!(pid.is_stable(0.05, None));
+  }
+}
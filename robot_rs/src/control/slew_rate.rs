@@ -0,0 +1,46 @@
+/// Limits the rate of change of a value, in units per second.
+#[derive(Debug, Clone)]
+pub struct SlewRateLimiter {
+  rate: f64,
+  last: Option<(f64, f64)>
+}
+
+impl SlewRateLimiter {
+  pub fn new(rate: f64) -> Self {
+    Self { rate, last: None }
+  }
+
+  pub fn reset(&mut self) {
+    self.last = None;
+  }
+
+  pub fn calculate(&mut self, value: f64, time: f64) -> f64 {
+    let output = match self.last {
+      Some((last_value, last_time)) => {
+        let max_change = self.rate * (time - last_time).max(0.0);
+        last_value + (value - last_value).clamp(-max_change, max_change)
+      },
+      None => value
+    };
+
+    self.last = Some((output, time));
+    output
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use approx::assert_relative_eq;
+
+  use super::SlewRateLimiter;
+
+  #[test]
+  fn test_slew_rate() {
+    let mut slew = SlewRateLimiter::new(2.0);
+    assert_relative_eq!(slew.calculate(0.0, 0.0), 0.0);
+    assert_relative_eq!(slew.calculate(1.0, 0.1), 0.2);
+    assert_relative_eq!(slew.calculate(1.0, 0.2), 0.4);
+    assert_relative_eq!(slew.calculate(-1.0, 0.3), 0.2);
+    assert_relative_eq!(slew.calculate(0.25, 1.0), 0.25);
+  }
+}
@@ -1,4 +1,6 @@
-use wpilib_hal::{HAL_ControlWord, HAL_GetControlWord, HAL_RefreshDSData};
+use std::ffi::CString;
+
+use wpilib_hal::{HAL_ControlWord, HAL_GetControlWord, HAL_RefreshDSData, HAL_SendError, WPI_EventHandle, WPI_CreateEvent, WPI_DestroyEvent, WPI_WaitForObjectTimeout, HAL_ProvideNewDataEventHandle, HAL_RemoveNewDataEventHandle};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ControlMode {
@@ -45,6 +47,59 @@ impl RobotControlState {
   }
 }
 
+/// An event that's signalled each time a new packet arrives from the Driver Station (every 20ms when connected)
+pub struct NewDataEvent(WPI_EventHandle);
+
+impl NewDataEvent {
+  pub fn new() -> Self {
+    let handle = unsafe { WPI_CreateEvent(0, 0) };
+    unsafe { HAL_ProvideNewDataEventHandle(handle) };
+    Self(handle)
+  }
+
+  /// Block until a new packet arrives, or the timeout (in seconds) elapses. Returns false if the timeout elapsed.
+  /// Once a packet has arrived, the latest data is made available to the joystick and control word functions.
+  pub fn wait_timeout(&self, timeout: f64) -> bool {
+    let mut timed_out = 0;
+    let signalled = unsafe { WPI_WaitForObjectTimeout(self.0, timeout, &mut timed_out) } != 0 && timed_out == 0;
+    if signalled {
+      unsafe { HAL_RefreshDSData() };
+    }
+    signalled
+  }
+}
+
+impl Default for NewDataEvent {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Drop for NewDataEvent {
+  fn drop(&mut self) {
+    unsafe {
+      HAL_RemoveNewDataEventHandle(self.0);
+      WPI_DestroyEvent(self.0);
+    }
+  }
+}
+
+/// Send a warning to the Driver Station console
+pub fn report_warning(message: &str) {
+  report(false, message)
+}
+
+/// Send an error to the Driver Station console
+pub fn report_error(message: &str) {
+  report(true, message)
+}
+
+fn report(is_error: bool, message: &str) {
+  let details = CString::new(message.replace('\0', "")).unwrap();
+  let empty = CString::default();
+  unsafe { HAL_SendError(is_error as i32, 1, 0, details.as_ptr(), empty.as_ptr(), empty.as_ptr(), 1) };
+}
+
 #[macro_export]
 macro_rules! robot_init {
   (
@@ -0,0 +1,139 @@
+use nalgebra::{SMatrix, SVector};
+
+use crate::models::linear_system::{discretize_ab, discretize_aq};
+
+use super::{kalman::covariance, numerical::{numerical_jacobian_x, rk4}, KalmanTypeFilter, Dynamics, MeasurementFn};
+
+/// An extended Kalman filter, which estimates the state of a nonlinear system by linearising its dynamics and
+/// measurements around the current estimate (using numerical Jacobians).
+///
+/// `f(x, u)` gives the continuous dynamics (dx/dt), and `h(x, u)` gives the expected measurement.
+pub struct ExtendedKalmanFilter<const S: usize, const I: usize, const O: usize> {
+  f: Dynamics<S, I>,
+  h: MeasurementFn<S, I, O>,
+  q: SMatrix<f64, S, S>,
+  r: SMatrix<f64, O, O>,
+  dt: f64,
+  x_hat: SVector<f64, S>,
+  p: SMatrix<f64, S, S>
+}
+
+impl<const S: usize, const I: usize, const O: usize> ExtendedKalmanFilter<S, I, O> {
+  /// Create a filter from the standard deviations of the process noise for each state and of each measurement,
+  /// with a nominal loop period of dt seconds.
+  pub fn new<F, H>(f: F, h: H, state_std_devs: [f64; S], measurement_std_devs: [f64; O], dt: f64) -> Self
+  where
+    F: Fn(&SVector<f64, S>, &SVector<f64, I>) -> SVector<f64, S> + Send + Sync + 'static,
+    H: Fn(&SVector<f64, S>, &SVector<f64, I>) -> SVector<f64, O> + Send + Sync + 'static
+  {
+    let q = covariance(state_std_devs);
+    Self { f: Box::new(f), h: Box::new(h), q, r: covariance(measurement_std_devs), dt, x_hat: SVector::zeros(), p: q }
+  }
+
+  pub fn reset(&mut self) {
+    self.x_hat = SVector::zeros();
+    self.p = self.q;
+  }
+
+  pub fn predict(&mut self, u: &SVector<f64, I>, dt: f64) {
+    let a = numerical_jacobian_x(&self.f, &self.x_hat, u);
+    let (a_d, _) = discretize_ab(&a, &SMatrix::<f64, S, I>::zeros(), dt);
+    let q_d = discretize_aq(&a, &self.q, dt);
+
+    self.x_hat = rk4(&self.f, &self.x_hat, u, dt);
+    self.p = a_d * self.p * a_d.transpose() + q_d;
+  }
+
+  pub fn correct(&mut self, u: &SVector<f64, I>, y: &SVector<f64, O>) {
+    let r = self.r / self.dt;
+    let (x_hat, p) = correct_with(&self.x_hat, &self.p, u, y, &self.h, &r);
+    self.x_hat = x_hat;
+    self.p = p;
+  }
+
+  /// Correct with a different kind of measurement than the one the filter was created with (e.g. a vision
+  /// measurement as well as encoders), with its own measurement function and noise standard deviations.
+  pub fn correct_other<const M: usize, H>(&mut self, u: &SVector<f64, I>, y: &SVector<f64, M>, h: H, measurement_std_devs: [f64; M])
+  where
+    H: Fn(&SVector<f64, S>, &SVector<f64, I>) -> SVector<f64, M>
+  {
+    let r = covariance(measurement_std_devs) / self.dt;
+    let (x_hat, p) = correct_with(&self.x_hat, &self.p, u, y, h, &r);
+    self.x_hat = x_hat;
+    self.p = p;
+  }
+}
+
+fn correct_with<const S: usize, const I: usize, const M: usize, H>(
+  x_hat: &SVector<f64, S>, p: &SMatrix<f64, S, S>, u: &SVector<f64, I>, y: &SVector<f64, M>, h: H, r: &SMatrix<f64, M, M>
+) -> (SVector<f64, S>, SMatrix<f64, S, S>)
+where
+  H: Fn(&SVector<f64, S>, &SVector<f64, I>) -> SVector<f64, M>
+{
+  let c = numerical_jacobian_x(&h, x_hat, u);
+  let s = c * p * c.transpose() + r;
+
+  let k = match s.try_inverse() {
+    Some(s_inv) => p * c.transpose() * s_inv,
+    None => return (*x_hat, *p)
+  };
+
+  let x_hat = x_hat + k * (y - h(x_hat, u));
+  // Joseph form, which keeps P symmetric and positive definite
+  let i_kc = SMatrix::<f64, S, S>::identity() - k * c;
+  let p = i_kc * p * i_kc.transpose() + k * r * k.transpose();
+  (x_hat, p)
+}
+
+impl<const S: usize, const I: usize, const O: usize> KalmanTypeFilter<S, I, O> for ExtendedKalmanFilter<S, I, O> {
+  fn x_hat(&self) -> &SVector<f64, S> { &self.x_hat }
+  fn set_x_hat(&mut self, x_hat: SVector<f64, S>) { self.x_hat = x_hat }
+  fn p(&self) -> &SMatrix<f64, S, S> { &self.p }
+  fn set_p(&mut self, p: SMatrix<f64, S, S>) { self.p = p }
+
+  fn predict(&mut self, u: &SVector<f64, I>, dt: f64) {
+    ExtendedKalmanFilter::predict(self, u, dt)
+  }
+
+  fn correct(&mut self, u: &SVector<f64, I>, y: &SVector<f64, O>) {
+    ExtendedKalmanFilter::correct(self, u, y)
+  }
+
+  fn correct_other<const M: usize, H>(&mut self, u: &SVector<f64, I>, y: &SVector<f64, M>, h: H, measurement_std_devs: [f64; M])
+  where
+    H: Fn(&SVector<f64, S>, &SVector<f64, I>) -> SVector<f64, M>
+  {
+    ExtendedKalmanFilter::correct_other(self, u, y, h, measurement_std_devs)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use approx::assert_relative_eq;
+  use nalgebra::{Vector1, Vector2};
+
+  use crate::estimation::{KalmanTypeFilter, numerical::rk4};
+
+  use super::ExtendedKalmanFilter;
+
+  // An arm with gravity: x = [angle from horizontal, angular velocity], u = [torque]
+  fn arm(x: &Vector2<f64>, u: &Vector1<f64>) -> Vector2<f64> {
+    Vector2::new(x[1], -9.81 * x[0].cos() + u[0] - 0.5 * x[1])
+  }
+
+  #[test]
+  fn test_ekf_arm() {
+    let mut ekf = ExtendedKalmanFilter::new(arm, |x: &Vector2<f64>, _u: &Vector1<f64>| Vector1::new(x[0]), [0.01, 0.1], [0.001], 0.02);
+    ekf.set_x_hat(Vector2::new(0.5, 0.0));
+
+    let mut x = Vector2::new(0.0, 0.0);
+    let u = Vector1::new(5.0);
+    for _ in 0..200 {
+      x = rk4(arm, &x, &u, 0.02);
+      ekf.predict(&u, 0.02);
+      ekf.correct(&u, &Vector1::new(x[0]));
+    }
+    assert_relative_eq!(ekf.x_hat()[0], x[0], epsilon = 1e-3);
+    assert_relative_eq!(ekf.x_hat()[1], x[1], epsilon = 1e-2);
+  }
+}
@@ -0,0 +1,95 @@
+use anyhow::anyhow;
+use nalgebra::{SMatrix, SVector};
+
+use crate::{models::{LinearSystem, linear_system::discretize_aq}, control::lqr::dare};
+
+/// Build a covariance matrix from standard deviations
+pub(crate) fn covariance<const N: usize>(std_devs: [f64; N]) -> SMatrix<f64, N, N> {
+  SMatrix::from_diagonal(&SVector::from_fn(|i, _| std_devs[i] * std_devs[i]))
+}
+
+/// A steady-state Kalman filter, which estimates the state of a linear system from noisy measurements. The Kalman
+/// gain is computed once, assuming a constant loop period.
+#[derive(Debug, Clone)]
+pub struct KalmanFilter<const S: usize, const I: usize, const O: usize> {
+  system: LinearSystem<S, I, O>,
+  k: SMatrix<f64, S, O>,
+  x_hat: SVector<f64, S>
+}
+
+impl<const S: usize, const I: usize, const O: usize> KalmanFilter<S, I, O> {
+  /// Create a filter, given the standard deviation of the process noise for each state (how much we trust the model),
+  /// the standard deviation of each measurement (how much we trust the sensors), and the loop period in seconds.
+  pub fn new(system: LinearSystem<S, I, O>, state_std_devs: [f64; S], measurement_std_devs: [f64; O], dt: f64) -> anyhow::Result<Self> {
+    let (a, _) = system.discretize(dt);
+    let q = discretize_aq(&system.a, &covariance(state_std_devs), dt);
+    let r = covariance(measurement_std_devs) / dt;
+
+    // The steady-state error covariance is the solution of the DARE for the dual (estimation) problem
+    let p = dare(&a.transpose(), &system.c.transpose(), &q, &r)
+      .ok_or_else(|| anyhow!("Could not solve the DARE. Is the system detectable?"))?;
+
+    let s = system.c * p * system.c.transpose() + r;
+    let k = p * system.c.transpose() * s.try_inverse().ok_or_else(|| anyhow!("Innovation covariance is not invertible"))?;
+
+    Ok(Self { system, k, x_hat: SVector::zeros() })
+  }
+
+  pub fn k(&self) -> &SMatrix<f64, S, O> { &self.k }
+
+  pub fn x_hat(&self) -> &SVector<f64, S> { &self.x_hat }
+
+  pub fn set_x_hat(&mut self, x_hat: SVector<f64, S>) {
+    self.x_hat = x_hat;
+  }
+
+  pub fn reset(&mut self) {
+    self.x_hat = SVector::zeros();
+  }
+
+  /// Project the state estimate forwards by dt seconds, with input u
+  pub fn predict(&mut self, u: &SVector<f64, I>, dt: f64) {
+    self.x_hat = self.system.calculate_x(&self.x_hat, u, dt);
+  }
+
+  /// Correct the state estimate with a measurement y, taken while input u was applied
+  pub fn correct(&mut self, u: &SVector<f64, I>, y: &SVector<f64, O>) {
+    self.x_hat += self.k * (y - self.system.calculate_y(&self.x_hat, u));
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use approx::assert_relative_eq;
+  use nalgebra::{Vector1, Vector2};
+
+  use crate::models::{plants::elevator, DcMotor};
+
+  use super::KalmanFilter;
+
+  #[test]
+  fn test_kalman_velocity() {
+    // Only position is measured, but the filter should also estimate velocity
+    let sys = elevator(&DcMotor::neo(), 5.0, 0.02, 10.0);
+    let mut kf = KalmanFilter::new(sys.clone(), [0.05, 1.0], [0.001], 0.02).unwrap();
+
+    let u = Vector1::new(3.0);
+    let mut x = Vector2::new(0.0, 0.0);
+    for _ in 0..100 {
+      x = sys.calculate_x(&x, &u, 0.02);
+      kf.predict(&u, 0.02);
+      kf.correct(&u, &Vector1::new(x[0]));
+    }
+    assert_relative_eq!(kf.x_hat()[0], x[0], epsilon = 1e-6);
+    assert_relative_eq!(kf.x_hat()[1], x[1], epsilon = 1e-3);
+
+    // Starting from the wrong state, the estimate should converge
+    kf.reset();
+    for _ in 0..100 {
+      x = sys.calculate_x(&x, &u, 0.02);
+      kf.predict(&u, 0.02);
+      kf.correct(&u, &Vector1::new(x[0]));
+    }
+    assert_relative_eq!(kf.x_hat()[1], x[1], epsilon = 1e-2);
+  }
+}
@@ -0,0 +1,256 @@
+use std::collections::VecDeque;
+
+use nalgebra::{SMatrix, SVector};
+
+use super::KalmanTypeFilter;
+
+// Timestamps closer than this are considered the same
+const TIME_EPSILON: f64 = 1e-9;
+
+/// A measurement correction, replayed with the input applied at the time of the measurement
+type Measurement<F, const I: usize> = Box<dyn Fn(&mut F, &SVector<f64, I>) + Send + Sync>;
+
+struct Snapshot<F, const S: usize, const I: usize> {
+  time: f64,
+  /// The input applied in the lead up to this time
+  u: SVector<f64, I>,
+  /// Measurements taken at this time, in the order they're applied
+  measurements: Vec<Measurement<F, I>>,
+  /// The filter's state after predicting to this time and applying the measurements
+  x_hat: SVector<f64, S>,
+  p: SMatrix<f64, S, S>
+}
+
+/// Applies measurements that arrive late (e.g. from a vision pipeline) at the time they were taken, rather than the
+/// time they were received. Keeps a timestamped history of the filter's inputs and measurements, rewinds to the
+/// measurement time, corrects, and replays the history back up to the present.
+pub struct LatencyCompensator<F, const S: usize, const I: usize, const O: usize> {
+  history: VecDeque<Snapshot<F, S, I>>,
+  history_length: f64
+}
+
+impl<F: KalmanTypeFilter<S, I, O>, const S: usize, const I: usize, const O: usize> LatencyCompensator<F, S, I, O> {
+  /// Create a compensator keeping history_length seconds of history. Measurements older than this are discarded.
+  pub fn new(history_length: f64) -> Self {
+    Self { history: VecDeque::new(), history_length }
+  }
+
+  /// Record the filter's state at the given time. Call this once per loop, after predicting with input u and
+  /// correcting with measurement y (if there was one), so they can be replayed when a late measurement arrives.
+  pub fn record(&mut self, filter: &F, u: &SVector<f64, I>, y: Option<&SVector<f64, O>>, time: f64) {
+    let measurements: Vec<Measurement<F, I>> = match y {
+      Some(y) => {
+        let y = *y;
+        vec![Box::new(move |filter: &mut F, u: &SVector<f64, I>| filter.correct(u, &y))]
+      },
+      None => vec![]
+    };
+
+    self.history.push_back(Snapshot { time, u: *u, measurements, x_hat: *filter.x_hat(), p: *filter.p() });
+
+    while let Some(front) = self.history.front() {
+      if time - front.time > self.history_length {
+        self.history.pop_front();
+      } else {
+        break;
+      }
+    }
+  }
+
+  /// Correct the filter with a measurement y taken at timestamp, replaying the recorded history so the filter ends at
+  /// the time of the latest recorded state. Measurements newer than the latest recorded state are applied to it
+  /// directly. Returns false if the measurement is older than the history.
+  pub fn apply_past_measurement(&mut self, filter: &mut F, y: &SVector<f64, O>, timestamp: f64) -> bool {
+    let y = *y;
+    self.apply(filter, Box::new(move |filter: &mut F, u: &SVector<f64, I>| filter.correct(u, &y)), timestamp)
+  }
+
+  /// As with apply_past_measurement, but for a different kind of measurement than the one the filter was created with
+  /// (see KalmanTypeFilter::correct_other).
+  pub fn apply_past_measurement_other<const M: usize, H>(&mut self, filter: &mut F, y: &SVector<f64, M>, h: H, measurement_std_devs: [f64; M], timestamp: f64) -> bool
+  where
+    H: Fn(&SVector<f64, S>, &SVector<f64, I>) -> SVector<f64, M> + Send + Sync + 'static
+  {
+    let y = *y;
+    self.apply(filter, Box::new(move |filter: &mut F, u: &SVector<f64, I>| filter.correct_other(u, &y, &h, measurement_std_devs)), timestamp)
+  }
+
+  fn apply(&mut self, filter: &mut F, measurement: Measurement<F, I>, timestamp: f64) -> bool {
+    let last = match self.history.back_mut() {
+      Some(last) => last,
+      None => return false
+    };
+
+    if timestamp > last.time + TIME_EPSILON {
+      // Newer than anything in the history, so there's nothing to replay
+      measurement(filter, &last.u);
+      last.measurements.push(measurement);
+      last.x_hat = *filter.x_hat();
+      last.p = *filter.p();
+      return true
+    }
+
+    // The first snapshot at or after the measurement. We need the one before it to start replaying from.
+    let idx = match self.history.iter().position(|s| s.time >= timestamp - TIME_EPSILON) {
+      Some(idx) if idx > 0 => idx,
+      _ => return false
+    };
+
+    if (self.history[idx].time - timestamp).abs() <= TIME_EPSILON {
+      self.history[idx].measurements.push(measurement);
+    } else {
+      // Over the interval leading up to the next snapshot, the same input was applied
+      let u = self.history[idx].u;
+      self.history.insert(idx, Snapshot { time: timestamp, u, measurements: vec![measurement], x_hat: SVector::zeros(), p: SMatrix::zeros() });
+    }
+
+    let start = &self.history[idx - 1];
+    filter.set_x_hat(start.x_hat);
+    filter.set_p(start.p);
+
+    let mut time = start.time;
+    for snapshot in self.history.iter_mut().skip(idx) {
+      filter.predict(&snapshot.u, snapshot.time - time);
+      for measurement in snapshot.measurements.iter() {
+        measurement(filter, &snapshot.u);
+      }
+      snapshot.x_hat = *filter.x_hat();
+      snapshot.p = *filter.p();
+      time = snapshot.time;
+    }
+    true
+  }
+
+  pub fn reset(&mut self) {
+    self.history.clear();
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use nalgebra::{Vector1, Vector2};
+
+  use crate::estimation::{ExtendedKalmanFilter, KalmanTypeFilter, numerical::rk4};
+
+  use super::LatencyCompensator;
+
+  // A cart under acceleration: x = [position, velocity], u = [acceleration]
+  fn cart(x: &Vector2<f64>, u: &Vector1<f64>) -> Vector2<f64> {
+    Vector2::new(x[1], u[0])
+  }
+
+  fn position(x: &Vector2<f64>, _u: &Vector1<f64>) -> Vector1<f64> {
+    Vector1::new(x[0])
+  }
+
+  fn velocity(x: &Vector2<f64>, _u: &Vector1<f64>) -> Vector1<f64> {
+    Vector1::new(x[1])
+  }
+
+  #[test]
+  fn test_delayed_measurement() {
+    let dt = 0.02;
+    let latency = 0.1;
+    let mut compensated = ExtendedKalmanFilter::new(cart, position, [0.1, 1.0], [0.01], dt);
+    let mut naive = ExtendedKalmanFilter::new(cart, position, [0.1, 1.0], [0.01], dt);
+    let mut compensator = LatencyCompensator::new(1.0);
+
+    let u = Vector1::new(0.0);
+    let truth = |t: f64| Vector2::new(2.0 * t, 2.0);
+
+    for i in 1..=200 {
+      let t = i as f64 * dt;
+      compensated.predict(&u, dt);
+      naive.predict(&u, dt);
+      compensator.record(&compensated, &u, None, t);
+
+      // The measurement we receive now was taken `latency` seconds ago
+      if t <= latency + dt { continue }
+      let y = position(&truth(t - latency), &u);
+      assert!(compensator.apply_past_measurement(&mut compensated, &y, t - latency));
+      naive.correct(&u, &y);
+    }
+
+    let x = truth(200.0 * dt);
+    let compensated_err = (compensated.x_hat()[0] - x[0]).abs();
+    let naive_err = (naive.x_hat()[0] - x[0]).abs();
+    assert!(compensated_err < 0.05, "{} vs {}", compensated_err, naive_err);
+    assert!(compensated_err < naive_err / 2.0, "{} vs {}", compensated_err, naive_err);
+  }
+
+  #[test]
+  fn test_keeps_local_corrections() {
+    // Encoder (velocity) corrections every loop, and a late (but exact) position measurement
+    let dt = 0.02;
+    let u = Vector1::new(2.0);
+    let mut filter = ExtendedKalmanFilter::new(cart, velocity, [0.1, 0.1], [0.01], dt);
+    let mut reference = ExtendedKalmanFilter::new(cart, velocity, [0.1, 0.1], [0.01], dt);
+    let mut compensator = LatencyCompensator::new(1.0);
+
+    let mut x = Vector2::zeros();
+    let mut positions = vec![];
+    for i in 1..=50 {
+      x = rk4(cart, &x, &u, dt);
+      positions.push(x[0]);
+      let y = velocity(&x, &u);
+
+      filter.predict(&u, dt);
+      filter.correct(&u, &y);
+      compensator.record(&filter, &u, Some(&y), i as f64 * dt);
+
+      reference.predict(&u, dt);
+      reference.correct(&u, &y);
+      if i == 35 {
+        // The same measurement, applied on time
+        reference.correct_other(&u, &Vector1::new(x[0]), position, [0.001]);
+      }
+    }
+
+    assert!(compensator.apply_past_measurement_other(&mut filter, &Vector1::new(positions[34]), position, [0.001], 35.0 * dt));
+    assert!((filter.x_hat() - reference.x_hat()).norm() < 1e-9, "{} vs {}", filter.x_hat(), reference.x_hat());
+    assert!((filter.x_hat()[0] - x[0]).abs() < 0.01);
+  }
+
+  #[test]
+  fn test_measurements_between_snapshots() {
+    // Two late measurements between the same pair of snapshots, arriving out of order, should give the same result
+    // as if they had been applied on time.
+    let dt = 0.02;
+    let u = Vector1::new(0.0);
+    let mut filter = ExtendedKalmanFilter::new(cart, position, [0.1, 1.0], [0.01], dt);
+    let mut reference = ExtendedKalmanFilter::new(cart, position, [0.1, 1.0], [0.01], dt);
+    let mut compensator = LatencyCompensator::new(1.0);
+
+    for i in 1..=10 {
+      let t = i as f64 * dt;
+      filter.predict(&u, dt);
+      compensator.record(&filter, &u, None, t);
+
+      if i == 5 {
+        reference.predict(&u, 0.005);
+        reference.correct(&u, &Vector1::new(0.2));
+        reference.predict(&u, 0.01);
+        reference.correct(&u, &Vector1::new(0.3));
+        reference.predict(&u, 0.005);
+      } else {
+        reference.predict(&u, dt);
+      }
+    }
+
+    assert!(compensator.apply_past_measurement(&mut filter, &Vector1::new(0.3), 0.095));
+    assert!(compensator.apply_past_measurement(&mut filter, &Vector1::new(0.2), 0.085));
+    assert!((filter.x_hat() - reference.x_hat()).norm() < 1e-9, "{} vs {}", filter.x_hat(), reference.x_hat());
+    assert!((filter.p() - reference.p()).norm() < 1e-9);
+  }
+
+  #[test]
+  fn test_too_old() {
+    let mut filter = ExtendedKalmanFilter::new(cart, position, [0.1, 0.1], [0.01], 0.02);
+    let mut compensator = LatencyCompensator::new(0.5);
+    for i in 0..100 {
+      compensator.record(&filter, &Vector1::new(0.0), None, i as f64 * 0.02);
+    }
+    assert!(!compensator.apply_past_measurement(&mut filter, &Vector1::new(1.0), 0.5));
+    assert!(compensator.apply_past_measurement(&mut filter, &Vector1::new(1.0), 1.9));
+  }
+}
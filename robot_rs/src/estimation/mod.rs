@@ -0,0 +1,36 @@
+pub mod ekf;
+pub mod kalman;
+pub mod latency;
+pub mod numerical;
+pub mod ukf;
+
+use nalgebra::{SMatrix, SVector};
+
+pub use ekf::ExtendedKalmanFilter;
+pub use kalman::KalmanFilter;
+pub use latency::LatencyCompensator;
+pub use ukf::UnscentedKalmanFilter;
+
+/// Continuous dynamics of a nonlinear system, dx/dt = f(x, u)
+pub type Dynamics<const S: usize, const I: usize> = Box<dyn Fn(&SVector<f64, S>, &SVector<f64, I>) -> SVector<f64, S> + Send + Sync>;
+/// Measurement function of a nonlinear system, y = h(x, u)
+pub type MeasurementFn<const S: usize, const I: usize, const O: usize> = Box<dyn Fn(&SVector<f64, S>, &SVector<f64, I>) -> SVector<f64, O> + Send + Sync>;
+
+/// Common interface for filters that track a state estimate and its error covariance, allowing the estimate to be
+/// rewound and replayed (see [LatencyCompensator]).
+pub trait KalmanTypeFilter<const S: usize, const I: usize, const O: usize> {
+  fn x_hat(&self) -> &SVector<f64, S>;
+  fn set_x_hat(&mut self, x_hat: SVector<f64, S>);
+  fn p(&self) -> &SMatrix<f64, S, S>;
+  fn set_p(&mut self, p: SMatrix<f64, S, S>);
+
+  /// Project the state estimate forwards by dt seconds, with input u
+  fn predict(&mut self, u: &SVector<f64, I>, dt: f64);
+  /// Correct the state estimate with a measurement y, taken while input u was applied
+  fn correct(&mut self, u: &SVector<f64, I>, y: &SVector<f64, O>);
+  /// Correct the state estimate with a different kind of measurement, with its own measurement function and noise
+  /// standard deviations
+  fn correct_other<const M: usize, H>(&mut self, u: &SVector<f64, I>, y: &SVector<f64, M>, h: H, measurement_std_devs: [f64; M])
+  where
+    H: Fn(&SVector<f64, S>, &SVector<f64, I>) -> SVector<f64, M>;
+}
@@ -0,0 +1,72 @@
+use nalgebra::{SMatrix, SVector};
+
+const EPSILON: f64 = 1e-5;
+
+/// The Jacobian of f at x, estimated with central differences
+pub fn numerical_jacobian<const R: usize, const C: usize, F>(f: F, x: &SVector<f64, C>) -> SMatrix<f64, R, C>
+where
+  F: Fn(&SVector<f64, C>) -> SVector<f64, R>
+{
+  let mut jacobian = SMatrix::<f64, R, C>::zeros();
+  for i in 0..C {
+    let mut dx = SVector::<f64, C>::zeros();
+    dx[i] = EPSILON;
+    jacobian.set_column(i, &((f(&(x + dx)) - f(&(x - dx))) / (2.0 * EPSILON)));
+  }
+  jacobian
+}
+
+/// The Jacobian of f(x, u) with respect to x
+pub fn numerical_jacobian_x<const R: usize, const S: usize, const I: usize, F>(f: F, x: &SVector<f64, S>, u: &SVector<f64, I>) -> SMatrix<f64, R, S>
+where
+  F: Fn(&SVector<f64, S>, &SVector<f64, I>) -> SVector<f64, R>
+{
+  numerical_jacobian(|x| f(x, u), x)
+}
+
+/// The Jacobian of f(x, u) with respect to u
+pub fn numerical_jacobian_u<const R: usize, const S: usize, const I: usize, F>(f: F, x: &SVector<f64, S>, u: &SVector<f64, I>) -> SMatrix<f64, R, I>
+where
+  F: Fn(&SVector<f64, S>, &SVector<f64, I>) -> SVector<f64, R>
+{
+  numerical_jacobian(|u| f(x, u), u)
+}
+
+/// Integrate dx/dt = f(x, u) over dt seconds with the 4th order Runge-Kutta method, holding u constant.
+pub fn rk4<const S: usize, const I: usize, F>(f: F, x: &SVector<f64, S>, u: &SVector<f64, I>, dt: f64) -> SVector<f64, S>
+where
+  F: Fn(&SVector<f64, S>, &SVector<f64, I>) -> SVector<f64, S>
+{
+  let k1 = f(x, u);
+  let k2 = f(&(x + k1 * (dt / 2.0)), u);
+  let k3 = f(&(x + k2 * (dt / 2.0)), u);
+  let k4 = f(&(x + k3 * dt), u);
+  x + (k1 + k2 * 2.0 + k3 * 2.0 + k4) * (dt / 6.0)
+}
+
+#[cfg(test)]
+mod test {
+  use approx::assert_relative_eq;
+  use nalgebra::{Vector1, Vector2, Matrix2x1};
+
+  use super::{numerical_jacobian_x, numerical_jacobian_u, rk4};
+
+  #[test]
+  fn test_jacobian() {
+    let f = |x: &Vector2<f64>, u: &Vector1<f64>| Vector2::new(x[0] * x[1], x[0].sin() + u[0] * u[0]);
+    let x = Vector2::new(0.5, 2.0);
+    let u = Vector1::new(3.0);
+    let jx = numerical_jacobian_x(f, &x, &u);
+    assert_relative_eq!(jx[(0, 0)], 2.0, epsilon = 1e-8);
+    assert_relative_eq!(jx[(0, 1)], 0.5, epsilon = 1e-8);
+    assert_relative_eq!(jx[(1, 0)], 0.5f64.cos(), epsilon = 1e-8);
+    assert_relative_eq!(numerical_jacobian_u(f, &x, &u), Matrix2x1::new(0.0, 6.0), epsilon = 1e-6);
+  }
+
+  #[test]
+  fn test_rk4() {
+    // dx/dt = x, so x(t) = e^t
+    let x = rk4(|x: &Vector1<f64>, _u: &Vector1<f64>| *x, &Vector1::new(1.0), &Vector1::zeros(), 1.0);
+    assert_relative_eq!(x[0], 1.0f64.exp(), epsilon = 1e-2);
+  }
+}
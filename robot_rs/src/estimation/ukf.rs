@@ -0,0 +1,202 @@
+use log::warn;
+use nalgebra::{SMatrix, SVector};
+
+use crate::models::linear_system::discretize_aq;
+
+use super::{kalman::covariance, numerical::{numerical_jacobian_x, rk4}, KalmanTypeFilter, Dynamics, MeasurementFn};
+
+/// Van der Merwe's scaled sigma points, and their weights
+struct SigmaPoints<const S: usize> {
+  lambda: f64,
+  wm: Vec<f64>,
+  wc: Vec<f64>
+}
+
+impl<const S: usize> SigmaPoints<S> {
+  fn new(alpha: f64, beta: f64, kappa: f64) -> Self {
+    let n = S as f64;
+    let lambda = alpha * alpha * (n + kappa) - n;
+
+    let w = 1.0 / (2.0 * (n + lambda));
+    let mut wm = vec![w; 2 * S + 1];
+    let mut wc = vec![w; 2 * S + 1];
+    wm[0] = lambda / (n + lambda);
+    wc[0] = lambda / (n + lambda) + (1.0 - alpha * alpha + beta);
+
+    Self { lambda, wm, wc }
+  }
+
+  fn points(&self, x: &SVector<f64, S>, p: &SMatrix<f64, S, S>) -> Vec<SVector<f64, S>> {
+    let scaled = (p + p.transpose()) / 2.0 * (S as f64 + self.lambda);
+    let l = match scaled.cholesky() {
+      Some(chol) => chol.l(),
+      None => {
+        // P has lost positive definiteness due to numerical error. Fall back to its diagonal.
+        warn!("UKF covariance is not positive definite, using its diagonal");
+        SMatrix::from_diagonal(&scaled.diagonal().map(|x| x.abs().sqrt()))
+      }
+    };
+
+    let mut points = Vec::with_capacity(2 * S + 1);
+    points.push(*x);
+    for i in 0..S {
+      points.push(x + l.column(i));
+    }
+    for i in 0..S {
+      points.push(x - l.column(i));
+    }
+    points
+  }
+}
+
+/// An unscented Kalman filter, which estimates the state of a nonlinear system by propagating a set of sample
+/// ("sigma") points through its dynamics and measurements. Handles strong nonlinearity better than the extended
+/// Kalman filter, at a higher computational cost.
+///
+/// `f(x, u)` gives the continuous dynamics (dx/dt), and `h(x, u)` gives the expected measurement.
+pub struct UnscentedKalmanFilter<const S: usize, const I: usize, const O: usize> {
+  f: Dynamics<S, I>,
+  h: MeasurementFn<S, I, O>,
+  q: SMatrix<f64, S, S>,
+  r: SMatrix<f64, O, O>,
+  dt: f64,
+  sigma: SigmaPoints<S>,
+  x_hat: SVector<f64, S>,
+  p: SMatrix<f64, S, S>
+}
+
+impl<const S: usize, const I: usize, const O: usize> UnscentedKalmanFilter<S, I, O> {
+  /// Create a filter from the standard deviations of the process noise for each state and of each measurement,
+  /// with a nominal loop period of dt seconds.
+  pub fn new<F, H>(f: F, h: H, state_std_devs: [f64; S], measurement_std_devs: [f64; O], dt: f64) -> Self
+  where
+    F: Fn(&SVector<f64, S>, &SVector<f64, I>) -> SVector<f64, S> + Send + Sync + 'static,
+    H: Fn(&SVector<f64, S>, &SVector<f64, I>) -> SVector<f64, O> + Send + Sync + 'static
+  {
+    let q = covariance(state_std_devs);
+    Self {
+      f: Box::new(f), h: Box::new(h),
+      q, r: covariance(measurement_std_devs), dt,
+      sigma: SigmaPoints::new(1e-3, 2.0, 3.0 - S as f64),
+      x_hat: SVector::zeros(),
+      p: q
+    }
+  }
+
+  pub fn reset(&mut self) {
+    self.x_hat = SVector::zeros();
+    self.p = self.q;
+  }
+
+  pub fn predict(&mut self, u: &SVector<f64, I>, dt: f64) {
+    let a = numerical_jacobian_x(&self.f, &self.x_hat, u);
+    let q_d = discretize_aq(&a, &self.q, dt);
+
+    let points: Vec<_> = self.sigma.points(&self.x_hat, &self.p).iter().map(|x| rk4(&self.f, x, u, dt)).collect();
+    let (x_hat, p) = self.unscented_transform(&points, &q_d);
+
+    self.x_hat = x_hat;
+    self.p = p;
+  }
+
+  pub fn correct(&mut self, u: &SVector<f64, I>, y: &SVector<f64, O>) {
+    let r = self.r / self.dt;
+    let (x_hat, p) = self.correct_with(u, y, &self.h, &r);
+    self.x_hat = x_hat;
+    self.p = p;
+  }
+
+  /// Correct with a different kind of measurement than the one the filter was created with (e.g. a vision
+  /// measurement as well as encoders), with its own measurement function and noise standard deviations.
+  pub fn correct_other<const M: usize, H>(&mut self, u: &SVector<f64, I>, y: &SVector<f64, M>, h: H, measurement_std_devs: [f64; M])
+  where
+    H: Fn(&SVector<f64, S>, &SVector<f64, I>) -> SVector<f64, M>
+  {
+    let r = covariance(measurement_std_devs) / self.dt;
+    let (x_hat, p) = self.correct_with(u, y, h, &r);
+    self.x_hat = x_hat;
+    self.p = p;
+  }
+
+  fn unscented_transform<const N: usize>(&self, points: &[SVector<f64, N>], noise: &SMatrix<f64, N, N>) -> (SVector<f64, N>, SMatrix<f64, N, N>) {
+    let mean: SVector<f64, N> = points.iter().zip(self.sigma.wm.iter()).map(|(x, w)| x * *w).sum();
+    let cov = points.iter().zip(self.sigma.wc.iter())
+      .map(|(x, w)| (x - mean) * (x - mean).transpose() * *w)
+      .fold(*noise, |acc, x| acc + x);
+    (mean, cov)
+  }
+
+  fn correct_with<const M: usize, H>(&self, u: &SVector<f64, I>, y: &SVector<f64, M>, h: H, r: &SMatrix<f64, M, M>) -> (SVector<f64, S>, SMatrix<f64, S, S>)
+  where
+    H: Fn(&SVector<f64, S>, &SVector<f64, I>) -> SVector<f64, M>
+  {
+    let points = self.sigma.points(&self.x_hat, &self.p);
+    let measurements: Vec<_> = points.iter().map(|x| h(x, u)).collect();
+    let (y_hat, p_y) = self.unscented_transform(&measurements, r);
+
+    let p_xy = points.iter().zip(measurements.iter()).zip(self.sigma.wc.iter())
+      .map(|((x, yi), w)| (x - self.x_hat) * (yi - y_hat).transpose() * *w)
+      .fold(SMatrix::<f64, S, M>::zeros(), |acc, x| acc + x);
+
+    match p_y.try_inverse() {
+      Some(p_y_inv) => {
+        let k = p_xy * p_y_inv;
+        (self.x_hat + k * (y - y_hat), self.p - k * p_y * k.transpose())
+      },
+      None => (self.x_hat, self.p)
+    }
+  }
+}
+
+impl<const S: usize, const I: usize, const O: usize> KalmanTypeFilter<S, I, O> for UnscentedKalmanFilter<S, I, O> {
+  fn x_hat(&self) -> &SVector<f64, S> { &self.x_hat }
+  fn set_x_hat(&mut self, x_hat: SVector<f64, S>) { self.x_hat = x_hat }
+  fn p(&self) -> &SMatrix<f64, S, S> { &self.p }
+  fn set_p(&mut self, p: SMatrix<f64, S, S>) { self.p = p }
+
+  fn predict(&mut self, u: &SVector<f64, I>, dt: f64) {
+    UnscentedKalmanFilter::predict(self, u, dt)
+  }
+
+  fn correct(&mut self, u: &SVector<f64, I>, y: &SVector<f64, O>) {
+    UnscentedKalmanFilter::correct(self, u, y)
+  }
+
+  fn correct_other<const M: usize, H>(&mut self, u: &SVector<f64, I>, y: &SVector<f64, M>, h: H, measurement_std_devs: [f64; M])
+  where
+    H: Fn(&SVector<f64, S>, &SVector<f64, I>) -> SVector<f64, M>
+  {
+    UnscentedKalmanFilter::correct_other(self, u, y, h, measurement_std_devs)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use approx::assert_relative_eq;
+  use nalgebra::{Vector1, Vector2};
+
+  use crate::estimation::{KalmanTypeFilter, numerical::rk4};
+
+  use super::UnscentedKalmanFilter;
+
+  fn arm(x: &Vector2<f64>, u: &Vector1<f64>) -> Vector2<f64> {
+    Vector2::new(x[1], -9.81 * x[0].cos() + u[0] - 0.5 * x[1])
+  }
+
+  #[test]
+  fn test_ukf_arm() {
+    // Measure the height of the end of a 1m arm, which is nonlinear in the angle
+    let mut ukf = UnscentedKalmanFilter::new(arm, |x: &Vector2<f64>, _u: &Vector1<f64>| Vector1::new(x[0].sin()), [0.01, 0.1], [0.001], 0.02);
+    ukf.set_x_hat(Vector2::new(0.3, 0.0));
+
+    let mut x = Vector2::new(0.0, 0.0);
+    let u = Vector1::new(5.0);
+    for _ in 0..200 {
+      x = rk4(arm, &x, &u, 0.02);
+      ukf.predict(&u, 0.02);
+      ukf.correct(&u, &Vector1::new(x[0].sin()));
+    }
+    assert_relative_eq!(ukf.x_hat()[0], x[0], epsilon = 1e-3);
+    assert_relative_eq!(ukf.x_hat()[1], x[1], epsilon = 1e-2);
+  }
+}
@@ -0,0 +1,185 @@
+use std::{collections::VecDeque, sync::Arc};
+
+use futures::{Stream, stream};
+use serde::{Serialize, Deserialize};
+use wpilib_hal::{HAL_JoystickButtons, HAL_GetJoystickButtons, HAL_JoystickAxes, HAL_GetJoystickAxes, HAL_JoystickPOVs, HAL_GetJoystickPOVs};
+
+use crate::ds::NewDataEvent;
+
+use super::hid::HID;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HIDEvent {
+  /// A button was pressed. Buttons are indexed from 1, as with HIDButton
+  Pressed { button: usize },
+  Released { button: usize },
+  AxisMoved { axis: usize, value: f64 },
+  PovChanged { pov: usize, angle: isize }
+}
+
+/// The state of all buttons, axes and POVs of a HID at a single point in time
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HIDSnapshot {
+  pub buttons: u32,
+  pub button_count: usize,
+  pub axes: Vec<f64>,
+  pub povs: Vec<isize>
+}
+
+impl HIDSnapshot {
+  pub fn capture(port: usize) -> Self {
+    let mut buttons = HAL_JoystickButtons::default();
+    let mut axes = HAL_JoystickAxes::default();
+    let mut povs = HAL_JoystickPOVs::default();
+    unsafe {
+      HAL_GetJoystickButtons(port as i32, &mut buttons);
+      HAL_GetJoystickAxes(port as i32, &mut axes);
+      HAL_GetJoystickPOVs(port as i32, &mut povs);
+    }
+
+    let n_axes = (axes.count.max(0) as usize).min(axes.axes.len());
+    let n_povs = (povs.count.max(0) as usize).min(povs.povs.len());
+
+    Self {
+      buttons: buttons.buttons,
+      button_count: buttons.count as usize,
+      axes: axes.axes[0..n_axes].iter().map(|x| *x as f64).collect(),
+      povs: povs.povs[0..n_povs].iter().map(|x| *x as isize).collect()
+    }
+  }
+
+  /// Get the state of a button, indexed from 1
+  pub fn button(&self, index: usize) -> bool {
+    if index > self.button_count || index < 1 {
+      false
+    } else {
+      self.buttons & (1 << (index - 1)) != 0
+    }
+  }
+
+  /// Update this snapshot with the next snapshot, returning the events that occurred between them. Axes are only
+  /// updated (and reported) once they've moved by more than the threshold, so slow drift still gets reported.
+  pub fn update(&mut self, next: &HIDSnapshot, axis_threshold: f64) -> Vec<HIDEvent> {
+    let mut events = vec![];
+
+    for button in 1..=self.button_count.max(next.button_count) {
+      match (self.button(button), next.button(button)) {
+        (false, true) => events.push(HIDEvent::Pressed { button }),
+        (true, false) => events.push(HIDEvent::Released { button }),
+        _ => ()
+      }
+    }
+
+    self.axes.resize(next.axes.len(), 0.0);
+    for (axis, (last, value)) in self.axes.iter_mut().zip(next.axes.iter()).enumerate() {
+      if (*value - *last).abs() > axis_threshold {
+        *last = *value;
+        events.push(HIDEvent::AxisMoved { axis, value: *value });
+      }
+    }
+
+    self.povs.resize(next.povs.len(), -1);
+    for (pov, (last, angle)) in self.povs.iter_mut().zip(next.povs.iter()).enumerate() {
+      if last != angle {
+        *last = *angle;
+        events.push(HIDEvent::PovChanged { pov, angle: *angle });
+      }
+    }
+
+    self.buttons = next.buttons;
+    self.button_count = next.button_count;
+    events
+  }
+}
+
+impl HID {
+  /// Create a stream of events from this HID. The HID is sampled once per driver station packet (20ms),
+  /// with all events that occurred in that packet being yielded in order of buttons, axes and then POVs.
+  pub fn events(&self) -> impl Stream<Item = HIDEvent> + Send + 'static {
+    self.events_with_threshold(0.01)
+  }
+
+  /// As with events(), but with a custom threshold for how far an axis has to move to be reported
+  pub fn events_with_threshold(&self, axis_threshold: f64) -> impl Stream<Item = HIDEvent> + Send + 'static {
+    let port = self.port();
+    // Registered before the initial capture, so no packet is missed in between
+    let new_data = Arc::new(NewDataEvent::new());
+    let initial = (HIDSnapshot::capture(port), VecDeque::new(), new_data);
+
+    stream::unfold(initial, move |(mut last, mut pending, new_data)| async move {
+      loop {
+        if let Some(event) = pending.pop_front() {
+          return Some((event, (last, pending, new_data)))
+        }
+
+        // The wait blocks, so is done off the async runtime. The timeout lets the blocking task finish soon after
+        // the stream is dropped.
+        let event = new_data.clone();
+        if let Ok(true) = tokio::task::spawn_blocking(move || event.wait_timeout(0.1)).await {
+          let next = HIDSnapshot::capture(port);
+          pending.extend(last.update(&next, axis_threshold));
+        }
+      }
+    })
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use futures::{Stream, StreamExt};
+  use tokio::time::{timeout, Duration};
+
+  use crate::input::{hid::HID, sim::{set_snapshot, notify_new_data}};
+
+  use super::{HIDSnapshot, HIDEvent};
+
+  #[test]
+  fn test_snapshot_update() {
+    let mut last = HIDSnapshot { buttons: 0b01, button_count: 2, axes: vec![0.0, 0.0], povs: vec![-1] };
+    let next = HIDSnapshot { buttons: 0b10, button_count: 2, axes: vec![0.005, 0.5], povs: vec![90] };
+
+    let events = last.update(&next, 0.01);
+    assert_eq!(events, vec![
+      HIDEvent::Released { button: 1 },
+      HIDEvent::Pressed { button: 2 },
+      HIDEvent::AxisMoved { axis: 1, value: 0.5 },
+      HIDEvent::PovChanged { pov: 0, angle: 90 }
+    ]);
+
+    // Axis 0 drifts past the threshold relative to the last reported value
+    let next = HIDSnapshot { axes: vec![0.015, 0.5], ..next };
+    assert_eq!(last.update(&next, 0.01), vec![ HIDEvent::AxisMoved { axis: 0, value: 0.015 } ]);
+    assert!(last.update(&next, 0.01).is_empty());
+  }
+
+  // The next event, or None if there isn't one within a second
+  async fn next_event<S: Stream<Item = HIDEvent> + Unpin>(events: &mut S) -> Option<HIDEvent> {
+    timeout(Duration::from_secs(1), events.next()).await.ok().flatten()
+  }
+
+  #[tokio::test]
+  async fn test_events_per_packet() {
+    let port = 3;
+    let snapshot = HIDSnapshot { buttons: 0b001, button_count: 3, axes: vec![0.0], povs: vec![-1] };
+    set_snapshot(port, &snapshot);
+    notify_new_data();
+
+    let mut events = Box::pin(HID::new(port).events());
+
+    // Nothing is reported until the Driver Station delivers a packet
+    set_snapshot(port, &HIDSnapshot { buttons: 0b110, ..snapshot.clone() });
+    assert_eq!(next_event(&mut events).await, None);
+
+    // One batch per packet, holding all the changes in that packet
+    notify_new_data();
+    assert_eq!(next_event(&mut events).await, Some(HIDEvent::Released { button: 1 }));
+    assert_eq!(next_event(&mut events).await, Some(HIDEvent::Pressed { button: 2 }));
+    assert_eq!(next_event(&mut events).await, Some(HIDEvent::Pressed { button: 3 }));
+    assert_eq!(next_event(&mut events).await, None);
+
+    set_snapshot(port, &HIDSnapshot { buttons: 0b010, ..snapshot.clone() });
+    notify_new_data();
+    assert_eq!(next_event(&mut events).await, Some(HIDEvent::Released { button: 3 }));
+    assert_eq!(next_event(&mut events).await, None);
+  }
+}
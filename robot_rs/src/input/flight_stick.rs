@@ -0,0 +1,33 @@
+use crate::macros::wrapped_traits_nogen;
+
+use super::hid::{HID, HIDAxis, HIDPOV, HIDButton, ControllerKind};
+
+/// Generic flight stick, such as the Logitech Extreme 3D Pro or Thrustmaster T.16000M.
+/// The remaining buttons differ between sticks, and are available through `button(n)`.
+pub struct FlightStick(HID);
+
+impl From<HID> for FlightStick {
+  fn from(value: HID) -> Self {
+    Self(value)
+  }
+}
+
+impl FlightStick {
+  pub fn new(port: usize) -> Self {
+    let hid = HID::new(port);
+    hid.expect_mapping(ControllerKind::FlightStick);
+    Self(hid)
+  }
+
+  pub fn x(&self) -> HIDAxis { self.axis(0) }
+  pub fn y(&self) -> HIDAxis { self.axis(1) }
+  pub fn twist(&self) -> HIDAxis { self.axis(2) }
+  pub fn throttle(&self) -> HIDAxis { self.axis(3) }
+
+  pub fn hat(&self) -> HIDPOV { self.pov(0) }
+
+  pub fn trigger(&self) -> HIDButton { self.button(1) }
+  pub fn thumb(&self) -> HIDButton { self.button(2) }
+}
+
+wrapped_traits_nogen!(FlightStick, HID);
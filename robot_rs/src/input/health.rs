@@ -0,0 +1,220 @@
+use log::warn;
+
+use crate::{ds::report_warning, time::now};
+
+use super::hid::{HID, HIDType, ControllerKind};
+
+/// The identity and layout of a controller, as reported by the Driver Station.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ControllerInfo {
+  pub name: String,
+  pub hid_type: HIDType,
+  pub kind: ControllerKind,
+  pub n_buttons: usize,
+  pub n_axes: usize,
+  pub n_povs: usize
+}
+
+impl ControllerInfo {
+  pub fn capture(hid: &HID) -> Self {
+    Self {
+      name: hid.name(),
+      hid_type: hid.hid_type(),
+      kind: hid.kind(),
+      n_buttons: hid.n_buttons(),
+      n_axes: hid.n_axes(),
+      n_povs: hid.n_pov()
+    }
+  }
+
+  pub fn is_connected(&self) -> bool {
+    self.n_buttons > 0 || self.n_axes > 0 || self.n_povs > 0
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConnectionStatus {
+  Connected,
+  Disconnected,
+  /// The controller is plugged in, but doesn't look like the one we expect. Contains the reason.
+  Mismatched(String)
+}
+
+impl ConnectionStatus {
+  pub fn is_ok(&self) -> bool {
+    matches!(self, ConnectionStatus::Connected)
+  }
+}
+
+/// What we expect to be plugged into a port. Any field left as None isn't checked.
+#[derive(Debug, Clone, Default)]
+pub struct ExpectedController {
+  pub kind: Option<ControllerKind>,
+  /// Case-insensitive substring of the controller name
+  pub name: Option<String>,
+  pub min_buttons: Option<usize>,
+  pub min_axes: Option<usize>,
+  pub min_povs: Option<usize>
+}
+
+impl ExpectedController {
+  pub fn kind(kind: ControllerKind) -> Self {
+    Self { kind: Some(kind), ..Default::default() }
+  }
+
+  pub fn check(&self, info: &ControllerInfo) -> ConnectionStatus {
+    if !info.is_connected() {
+      return ConnectionStatus::Disconnected
+    }
+
+    if let Some(kind) = self.kind {
+      if info.kind != ControllerKind::Unknown && info.kind != kind {
+        return ConnectionStatus::Mismatched(format!("looks like a {:?} ({:?}), expected a {:?}", info.kind, info.hid_type, kind))
+      }
+    }
+
+    if let Some(name) = &self.name {
+      if !info.name.to_lowercase().contains(&name.to_lowercase()) {
+        return ConnectionStatus::Mismatched(format!("expected a controller named \"{}\"", name))
+      }
+    }
+
+    let counts = [
+      ("buttons", self.min_buttons, info.n_buttons),
+      ("axes", self.min_axes, info.n_axes),
+      ("POVs", self.min_povs, info.n_povs)
+    ];
+    for (what, min, actual) in counts {
+      if let Some(min) = min {
+        if actual < min {
+          return ConnectionStatus::Mismatched(format!("has {} {}, expected at least {}", actual, what, min))
+        }
+      }
+    }
+
+    ConnectionStatus::Connected
+  }
+}
+
+/// Monitors the controller on a port, warning (in the log and on the Driver Station console) when
+/// it's unplugged or doesn't match what's expected. Warnings are given immediately when the status
+/// changes, and repeated at most once every `warn_interval` seconds while the problem persists.
+pub struct ConnectionMonitor {
+  hid: HID,
+  expected: ExpectedController,
+  warn_interval: f64,
+  status: Option<ConnectionStatus>,
+  last_warning: Option<f64>
+}
+
+impl ConnectionMonitor {
+  pub fn new(port: usize, expected: ExpectedController) -> Self {
+    Self {
+      hid: HID::new(port),
+      expected,
+      warn_interval: 10.0,
+      status: None,
+      last_warning: None
+    }
+  }
+
+  pub fn with_warn_interval(mut self, interval: f64) -> Self {
+    self.warn_interval = interval;
+    self
+  }
+
+  pub fn port(&self) -> usize { self.hid.port() }
+
+  /// The status as of the last update, or None if the monitor hasn't been updated yet
+  pub fn status(&self) -> Option<&ConnectionStatus> {
+    self.status.as_ref()
+  }
+
+  /// Check the controller, warning if needed.
+  pub fn update(&mut self) -> ConnectionStatus {
+    let info = ControllerInfo::capture(&self.hid);
+    let status = self.expected.check(&info);
+    if let Some(message) = self.update_status(status.clone(), now()) {
+      let message = format!("Controller on port {} (\"{}\") {}", self.port(), info.name, message);
+      warn!("{}", message);
+      report_warning(&message);
+    }
+    status
+  }
+
+  /// Update the status, returning a warning message if one should be given at this time.
+  fn update_status(&mut self, status: ConnectionStatus, time: f64) -> Option<String> {
+    let changed = self.status.as_ref() != Some(&status);
+    self.status = Some(status.clone());
+
+    let message = match status {
+      ConnectionStatus::Connected => {
+        self.last_warning = None;
+        return None
+      },
+      ConnectionStatus::Disconnected => "is disconnected".to_owned(),
+      ConnectionStatus::Mismatched(reason) => reason
+    };
+
+    let due = self.last_warning.map(|last| time - last >= self.warn_interval).unwrap_or(true);
+    if changed || due {
+      self.last_warning = Some(time);
+      Some(message)
+    } else {
+      None
+    }
+  }
+
+  /// Check the controller every 20ms, forever
+  pub async fn run(mut self) {
+    loop {
+      self.update();
+      tokio::time::sleep(tokio::time::Duration::from_millis(20)).await;
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use crate::input::hid::{HIDType, ControllerKind};
+
+  use super::{ControllerInfo, ConnectionStatus, ExpectedController, ConnectionMonitor};
+
+  fn xbox() -> ControllerInfo {
+    ControllerInfo {
+      name: "Xbox Controller".to_owned(),
+      hid_type: HIDType::XInputGamepad,
+      kind: ControllerKind::Xbox,
+      n_buttons: 10,
+      n_axes: 6,
+      n_povs: 1
+    }
+  }
+
+  #[test]
+  fn test_check() {
+    let expected = ExpectedController { min_axes: Some(6), ..ExpectedController::kind(ControllerKind::Xbox) };
+    assert_eq!(expected.check(&xbox()), ConnectionStatus::Connected);
+
+    let unplugged = ControllerInfo { n_buttons: 0, n_axes: 0, n_povs: 0, ..xbox() };
+    assert_eq!(expected.check(&unplugged), ConnectionStatus::Disconnected);
+
+    let ps4 = ControllerInfo { kind: ControllerKind::PS4, ..xbox() };
+    assert!(!expected.check(&ps4).is_ok());
+
+    let few_axes = ControllerInfo { n_axes: 4, ..xbox() };
+    assert!(!expected.check(&few_axes).is_ok());
+  }
+
+  #[test]
+  fn test_rate_limit() {
+    let mut monitor = ConnectionMonitor::new(0, ExpectedController::default()).with_warn_interval(5.0);
+    assert!(monitor.update_status(ConnectionStatus::Connected, 0.0).is_none());
+    assert!(monitor.update_status(ConnectionStatus::Disconnected, 1.0).is_some());
+    assert!(monitor.update_status(ConnectionStatus::Disconnected, 2.0).is_none());
+    assert!(monitor.update_status(ConnectionStatus::Disconnected, 6.0).is_some());
+    assert!(monitor.update_status(ConnectionStatus::Mismatched("wrong".to_owned()), 6.5).is_some());
+    assert!(monitor.update_status(ConnectionStatus::Connected, 7.0).is_none());
+    assert!(monitor.update_status(ConnectionStatus::Disconnected, 7.5).is_some());
+  }
+}
@@ -1,11 +1,16 @@
-use std::ffi::CStr;
+use std::{ffi::CStr, sync::{Mutex, atomic::{AtomicU8, Ordering}}};
 
-use wpilib_hal::{HAL_GetJoystickButtons, HAL_JoystickButtons, HAL_JoystickAxes, HAL_GetJoystickAxes, HAL_JoystickPOVs, HAL_GetJoystickPOVs, HAL_JoystickDescriptor, HAL_GetJoystickDescriptor};
+use log::warn;
+use serde::{Serialize, Deserialize};
+use wpilib_hal::{HAL_GetJoystickButtons, HAL_JoystickButtons, HAL_JoystickAxes, HAL_GetJoystickAxes, HAL_JoystickPOVs, HAL_GetJoystickPOVs, HAL_JoystickDescriptor, HAL_GetJoystickDescriptor, HAL_SetJoystickOutputs};
 
 use crate::{sensors::{digital::DigitalInput, analog::AnalogInput}, control::edge_detect::{Edge, EdgeDetector, EdgeDetectorOwned}};
 
+use super::{shaping::ShapedAxis, trigger::{AxisButton, POVButton}};
+
 /* BUTTONS */
 
+#[derive(Clone)]
 pub struct HIDButton {
   port: usize,
   index: usize
@@ -16,6 +21,8 @@ impl HIDButton {
     HIDButton { port: hid.port, index }
   }
 
+  pub fn index(&self) -> usize { self.index }
+
   pub fn edge(&self, edge: Edge) -> EdgeDetector {
     EdgeDetector::new(self, edge)
   }
@@ -27,6 +34,7 @@ impl HIDButton {
 
 impl DigitalInput for HIDButton {
   fn get(&self) -> bool {
+    check_pending_mapping(self.port);
     let mut buttons = HAL_JoystickButtons::default();
     unsafe { HAL_GetJoystickButtons(self.port as i32, &mut buttons) };
     if self.index > buttons.count as usize || self.index < 1 {
@@ -39,6 +47,7 @@ impl DigitalInput for HIDButton {
 
 /* AXES */
 
+#[derive(Clone)]
 pub struct HIDAxis {
   port: usize,
   index: usize
@@ -48,10 +57,28 @@ impl HIDAxis {
   pub fn new(hid: &HID, index: usize) -> Self {
     Self { port: hid.port, index }
   }
+
+  pub fn index(&self) -> usize { self.index }
+
+  /// Apply shaping (deadband, response curve, etc) to this axis
+  pub fn shaped(self) -> ShapedAxis<Self> {
+    ShapedAxis::new(self)
+  }
+
+  /// Use this axis as a button, active when the axis is greater than the threshold
+  pub fn above(self, threshold: f64) -> AxisButton<Self> {
+    AxisButton::above(self, threshold)
+  }
+
+  /// Use this axis as a button, active when the axis is less than the threshold
+  pub fn below(self, threshold: f64) -> AxisButton<Self> {
+    AxisButton::below(self, threshold)
+  }
 }
 
 impl AnalogInput for HIDAxis {
   fn get(&self) -> f64 {
+    check_pending_mapping(self.port);
     let mut axes = HAL_JoystickAxes::default();
     unsafe { HAL_GetJoystickAxes(self.port as i32, &mut axes) };
     *axes.axes.get(self.index).unwrap_or(&0.0) as f64
@@ -60,6 +87,7 @@ impl AnalogInput for HIDAxis {
 
 /* POVs */
 
+#[derive(Clone)]
 pub struct HIDPOV {
   port: usize,
   index: usize
@@ -70,15 +98,196 @@ impl HIDPOV {
     Self { port: hid.port, index }
   }
 
+  pub fn index(&self) -> usize { self.index }
+
   pub fn get(&self) -> isize {
+    check_pending_mapping(self.port);
     let mut povs = HAL_JoystickPOVs::default();
     unsafe { HAL_GetJoystickPOVs(self.port as i32, &mut povs) };
     *povs.povs.get(self.index).unwrap_or(&-1) as isize
   }
+
+  /// Use a direction of this POV as a button, where angle is in degrees (0 = up, 90 = right)
+  pub fn direction(&self, angle: isize) -> POVButton {
+    POVButton::new(self.clone(), angle)
+  }
+
+  pub fn up(&self) -> POVButton { self.direction(0) }
+  pub fn up_right(&self) -> POVButton { self.direction(45) }
+  pub fn right(&self) -> POVButton { self.direction(90) }
+  pub fn down_right(&self) -> POVButton { self.direction(135) }
+  pub fn down(&self) -> POVButton { self.direction(180) }
+  pub fn down_left(&self) -> POVButton { self.direction(225) }
+  pub fn left(&self) -> POVButton { self.direction(270) }
+  pub fn up_left(&self) -> POVButton { self.direction(315) }
 }
 
 /* HID */
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HIDType {
+  Unknown,
+  XInputUnknown,
+  XInputGamepad,
+  XInputWheel,
+  XInputArcadeStick,
+  XInputFlightStick,
+  XInputDancePad,
+  XInputGuitar,
+  XInputGuitar2,
+  XInputDrumKit,
+  XInputGuitar3,
+  XInputArcadePad,
+  HIDJoystick,
+  HIDGamepad,
+  HIDDriving,
+  HIDFlight,
+  HID1stPerson
+}
+
+impl From<u8> for HIDType {
+  fn from(value: u8) -> Self {
+    match value {
+      0 => HIDType::XInputUnknown,
+      1 => HIDType::XInputGamepad,
+      2 => HIDType::XInputWheel,
+      3 => HIDType::XInputArcadeStick,
+      4 => HIDType::XInputFlightStick,
+      5 => HIDType::XInputDancePad,
+      6 => HIDType::XInputGuitar,
+      7 => HIDType::XInputGuitar2,
+      8 => HIDType::XInputDrumKit,
+      11 => HIDType::XInputGuitar3,
+      19 => HIDType::XInputArcadePad,
+      20 => HIDType::HIDJoystick,
+      21 => HIDType::HIDGamepad,
+      22 => HIDType::HIDDriving,
+      23 => HIDType::HIDFlight,
+      24 => HIDType::HID1stPerson,
+      _ => HIDType::Unknown
+    }
+  }
+}
+
+impl From<HIDType> for u8 {
+  fn from(value: HIDType) -> Self {
+    match value {
+      HIDType::Unknown => 255,
+      HIDType::XInputUnknown => 0,
+      HIDType::XInputGamepad => 1,
+      HIDType::XInputWheel => 2,
+      HIDType::XInputArcadeStick => 3,
+      HIDType::XInputFlightStick => 4,
+      HIDType::XInputDancePad => 5,
+      HIDType::XInputGuitar => 6,
+      HIDType::XInputGuitar2 => 7,
+      HIDType::XInputDrumKit => 8,
+      HIDType::XInputGuitar3 => 11,
+      HIDType::XInputArcadePad => 19,
+      HIDType::HIDJoystick => 20,
+      HIDType::HIDGamepad => 21,
+      HIDType::HIDDriving => 22,
+      HIDType::HIDFlight => 23,
+      HIDType::HID1stPerson => 24
+    }
+  }
+}
+
+/// The kind of controller plugged into a port, as best as can be determined from its descriptor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControllerKind {
+  Xbox,
+  PS4,
+  PS5,
+  FlightStick,
+  Unknown
+}
+
+impl ControllerKind {
+  /// Detect the kind of controller from the parts of its descriptor
+  pub fn detect(name: &str, hid_type: HIDType, is_xbox: bool, n_buttons: usize) -> Self {
+    let name = name.to_lowercase();
+
+    if is_xbox || name.contains("xbox") {
+      ControllerKind::Xbox
+    } else if name.contains("dualsense") {
+      ControllerKind::PS5
+    } else if name.contains("dualshock") {
+      ControllerKind::PS4
+    } else if name.contains("wireless controller") {
+      // Both the DualShock 4 and DualSense enumerate as "Wireless Controller". The DualSense has an extra
+      // (mic mute) button.
+      if n_buttons >= 15 { ControllerKind::PS5 } else { ControllerKind::PS4 }
+    } else if name.contains("extreme 3d") || name.contains("t.16000") || name.contains("thrustmaster") {
+      ControllerKind::FlightStick
+    } else {
+      match hid_type {
+        HIDType::HIDFlight | HIDType::XInputFlightStick => ControllerKind::FlightStick,
+        _ => ControllerKind::Unknown
+      }
+    }
+  }
+
+  fn to_u8(self) -> u8 {
+    match self {
+      ControllerKind::Unknown => 0,
+      ControllerKind::Xbox => 1,
+      ControllerKind::PS4 => 2,
+      ControllerKind::PS5 => 3,
+      ControllerKind::FlightStick => 4
+    }
+  }
+
+  fn from_u8(value: u8) -> Option<Self> {
+    match value {
+      1 => Some(ControllerKind::Xbox),
+      2 => Some(ControllerKind::PS4),
+      3 => Some(ControllerKind::PS5),
+      4 => Some(ControllerKind::FlightStick),
+      _ => None
+    }
+  }
+}
+
+const N_PORTS: usize = 6;
+
+// The mapping expected on each port, waiting to be checked once a controller connects (0 if there's nothing to check).
+// Controllers are usually created before the Driver Station has sent any descriptors, so we check on the first read
+// after the controller connects.
+static PENDING_MAPPING_CHECKS: [AtomicU8; N_PORTS] = [
+  AtomicU8::new(0), AtomicU8::new(0), AtomicU8::new(0), AtomicU8::new(0), AtomicU8::new(0), AtomicU8::new(0)
+];
+
+fn check_pending_mapping(port: usize) {
+  let pending = match PENDING_MAPPING_CHECKS.get(port) {
+    Some(pending) => pending,
+    None => return
+  };
+
+  if let Some(expected) = ControllerKind::from_u8(pending.load(Ordering::Relaxed)) {
+    let hid = HID::new(port);
+    if hid.is_connected() && pending.swap(0, Ordering::Relaxed) != 0 {
+      hid.check_mapping(expected);
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct HIDOutputs {
+  outputs: u32,
+  left_rumble: u16,
+  right_rumble: u16
+}
+
+impl HIDOutputs {
+  const fn new() -> Self {
+    Self { outputs: 0, left_rumble: 0, right_rumble: 0 }
+  }
+}
+
+// Outputs and rumble are written for a whole port at once, so are shared between every HID on the same port
+static HID_OUTPUTS: Mutex<[HIDOutputs; N_PORTS]> = Mutex::new([HIDOutputs::new(); N_PORTS]);
+
 pub struct HID {
   port: usize
 }
@@ -123,4 +332,136 @@ impl HID {
   pub fn name(&self) -> String {
     unsafe { CStr::from_ptr(self.descriptor().name.as_ptr()).to_string_lossy().to_string() }
   }
+
+  pub fn hid_type(&self) -> HIDType {
+    self.descriptor().type_.into()
+  }
+
+  /// Whether a controller is plugged into this port. Buttons and axes read as false and 0 while
+  /// the controller is disconnected.
+  pub fn is_connected(&self) -> bool {
+    let descriptor = self.descriptor();
+    descriptor.buttonCount > 0 || descriptor.axisCount > 0 || descriptor.povCount > 0
+  }
+
+  pub fn is_xbox(&self) -> bool {
+    self.descriptor().isXbox != 0
+  }
+
+  /// Detect the kind of controller plugged into this port from its name and descriptor.
+  pub fn kind(&self) -> ControllerKind {
+    let descriptor = self.descriptor();
+    ControllerKind::detect(&self.name(), descriptor.type_.into(), descriptor.isXbox != 0, descriptor.buttonCount as usize)
+  }
+
+  /// Set the rumble of the controller, where left and right are in the range 0..1
+  pub fn set_rumble(&self, left: f64, right: f64) {
+    self.update_outputs(|outputs| {
+      outputs.left_rumble = (left.clamp(0.0, 1.0) * u16::MAX as f64) as u16;
+      outputs.right_rumble = (right.clamp(0.0, 1.0) * u16::MAX as f64) as u16;
+    });
+  }
+
+  /// Set a single HID output bit, where index starts at 1. Indices outside of 1..=32 are ignored.
+  pub fn set_output(&self, index: usize, value: bool) {
+    if !(1..=32).contains(&index) {
+      warn!("HID output index {} on port {} is out of range (1..=32)", index, self.port);
+      return
+    }
+
+    let mask = 1 << (index - 1);
+    self.update_outputs(|outputs| {
+      outputs.outputs = if value { outputs.outputs | mask } else { outputs.outputs & !mask };
+    });
+  }
+
+  /// Set all HID output bits at once
+  pub fn set_outputs(&self, value: u32) {
+    self.update_outputs(|outputs| outputs.outputs = value);
+  }
+
+  /// The HID output bits last set on this port
+  pub fn outputs(&self) -> u32 {
+    HID_OUTPUTS.lock().unwrap().get(self.port).map(|o| o.outputs).unwrap_or(0)
+  }
+
+  fn update_outputs<F: FnOnce(&mut HIDOutputs)>(&self, f: F) {
+    let mut all = HID_OUTPUTS.lock().unwrap();
+    if let Some(outputs) = all.get_mut(self.port) {
+      f(outputs);
+      unsafe { HAL_SetJoystickOutputs(self.port as i32, outputs.outputs as i64, outputs.left_rumble as i32, outputs.right_rumble as i32) };
+    }
+  }
+
+  /// Check that the controller plugged into this port matches the mapping we expect to use, logging a warning
+  /// if it doesn't. Returns true if the controller matches, or its kind can't be determined (e.g. it's not plugged in).
+  pub fn check_mapping(&self, expected: ControllerKind) -> bool {
+    if !self.is_connected() {
+      return true
+    }
+
+    match self.kind() {
+      ControllerKind::Unknown => true,
+      kind if kind == expected => true,
+      kind => {
+        warn!("Controller on port {} (\"{}\") looks like a {:?}, but is mapped as a {:?}", self.port, self.name(), kind, expected);
+        false
+      }
+    }
+  }
+
+  /// Check the mapping as in [HID::check_mapping] if a controller is plugged in, otherwise wait to check it until
+  /// the first read of a button, axis or POV on this port after a controller connects.
+  pub fn expect_mapping(&self, expected: ControllerKind) {
+    if self.is_connected() {
+      self.check_mapping(expected);
+    } else if let Some(pending) = PENDING_MAPPING_CHECKS.get(self.port) {
+      pending.store(expected.to_u8(), Ordering::Relaxed);
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use crate::input::sim::set_descriptor;
+
+  use super::{ControllerKind, HIDType, HID};
+
+  #[test]
+  fn test_detect() {
+    assert_eq!(ControllerKind::detect("Xbox Controller", HIDType::XInputGamepad, true, 10), ControllerKind::Xbox);
+    assert_eq!(ControllerKind::detect("DualSense Wireless Controller", HIDType::HIDGamepad, false, 15), ControllerKind::PS5);
+    assert_eq!(ControllerKind::detect("Wireless Controller", HIDType::HIDGamepad, false, 14), ControllerKind::PS4);
+    assert_eq!(ControllerKind::detect("Wireless Controller", HIDType::HIDGamepad, false, 15), ControllerKind::PS5);
+    assert_eq!(ControllerKind::detect("Logitech Extreme 3D", HIDType::HIDJoystick, false, 12), ControllerKind::FlightStick);
+    assert_eq!(ControllerKind::detect("Generic Stick", HIDType::HIDFlight, false, 4), ControllerKind::FlightStick);
+    assert_eq!(ControllerKind::detect("Generic Pad", HIDType::HIDGamepad, false, 8), ControllerKind::Unknown);
+  }
+
+  #[test]
+  fn test_set_output_out_of_range() {
+    let hid = HID::new(5);
+    hid.set_output(0, true);
+    hid.set_output(33, true);
+    assert_eq!(hid.outputs(), 0);
+  }
+
+  #[test]
+  fn test_outputs_shared() {
+    HID::new(4).set_output(3, true);
+    let other = HID::new(4);
+    other.set_rumble(0.5, 0.5);
+    assert_eq!(other.outputs(), 0b100);
+  }
+
+  #[test]
+  fn test_kind() {
+    let hid = HID::new(3);
+    set_descriptor(3, "Wireless Controller", HIDType::HIDGamepad, false, 14, 6, 1);
+    assert_eq!(hid.kind(), ControllerKind::PS4);
+    set_descriptor(3, "Wireless Controller", HIDType::HIDGamepad, false, 15, 6, 1);
+    assert_eq!(hid.kind(), ControllerKind::PS5);
+    set_descriptor(3, "Controller (Xbox One For Windows)", HIDType::XInputGamepad, true, 10, 6, 1);
+    assert_eq!(hid.kind(), ControllerKind::Xbox);
+  }
 }
\ No newline at end of file
@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+
+use super::sim::SimHID;
+
+// Drive a simulated joystick from the keyboard, for testing the simulated robot from a laptop without a controller.
+// Most terminals only report key presses (and repeats), not releases, so a key is considered held until it hasn't
+// been seen for `hold_time` seconds. This needs to be longer than the keyboard's initial repeat delay.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Key {
+  Char(char),
+  Up,
+  Down,
+  Left,
+  Right
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeyBinding {
+  /// Hold a button, where the index starts at 1
+  Button(usize),
+  /// Push an axis to the given value. Multiple keys on the same axis are summed, so opposing keys cancel out.
+  Axis { axis: usize, value: f64 },
+  /// Hold a POV in the given direction, in degrees
+  Pov { pov: usize, angle: isize }
+}
+
+pub struct KeyboardJoystick {
+  sim: SimHID,
+  bindings: HashMap<Key, KeyBinding>,
+  held: HashMap<Key, f64>,
+  hold_time: f64
+}
+
+impl KeyboardJoystick {
+  pub fn new(sim: SimHID) -> Self {
+    Self { sim, bindings: HashMap::new(), held: HashMap::new(), hold_time: 0.6 }
+  }
+
+  /// A simulated Xbox controller, with WASD on the left stick, IJKL on the right stick, the arrow keys on the dpad,
+  /// Q/E on the triggers, space / F / R / T on A / B / X / Y, and Z / C on the bumpers.
+  pub fn xbox(port: usize) -> Self {
+    Self::new(SimHID::xbox(port))
+      .bind(Key::Char('w'), KeyBinding::Axis { axis: 1, value: -1.0 })
+      .bind(Key::Char('s'), KeyBinding::Axis { axis: 1, value: 1.0 })
+      .bind(Key::Char('a'), KeyBinding::Axis { axis: 0, value: -1.0 })
+      .bind(Key::Char('d'), KeyBinding::Axis { axis: 0, value: 1.0 })
+      .bind(Key::Char('q'), KeyBinding::Axis { axis: 2, value: 1.0 })
+      .bind(Key::Char('e'), KeyBinding::Axis { axis: 3, value: 1.0 })
+      .bind(Key::Char('j'), KeyBinding::Axis { axis: 4, value: -1.0 })
+      .bind(Key::Char('l'), KeyBinding::Axis { axis: 4, value: 1.0 })
+      .bind(Key::Char('i'), KeyBinding::Axis { axis: 5, value: -1.0 })
+      .bind(Key::Char('k'), KeyBinding::Axis { axis: 5, value: 1.0 })
+      .bind(Key::Up, KeyBinding::Pov { pov: 0, angle: 0 })
+      .bind(Key::Right, KeyBinding::Pov { pov: 0, angle: 90 })
+      .bind(Key::Down, KeyBinding::Pov { pov: 0, angle: 180 })
+      .bind(Key::Left, KeyBinding::Pov { pov: 0, angle: 270 })
+      .bind(Key::Char(' '), KeyBinding::Button(1))
+      .bind(Key::Char('f'), KeyBinding::Button(2))
+      .bind(Key::Char('r'), KeyBinding::Button(3))
+      .bind(Key::Char('t'), KeyBinding::Button(4))
+      .bind(Key::Char('z'), KeyBinding::Button(5))
+      .bind(Key::Char('c'), KeyBinding::Button(6))
+  }
+
+  pub fn bind(mut self, key: Key, binding: KeyBinding) -> Self {
+    self.bindings.insert(key, binding);
+    self
+  }
+
+  pub fn with_hold_time(mut self, hold_time: f64) -> Self {
+    self.hold_time = hold_time;
+    self
+  }
+
+  pub fn sim(&self) -> &SimHID { &self.sim }
+
+  pub fn key_down(&mut self, key: Key, time: f64) {
+    self.held.insert(key, time);
+  }
+
+  pub fn key_up(&mut self, key: Key) {
+    self.held.remove(&key);
+  }
+
+  /// Release any keys that have timed out, and push the resulting state to the simulated joystick
+  pub fn update(&mut self, time: f64) {
+    let hold_time = self.hold_time;
+    self.held.retain(|_, last| time - *last < hold_time);
+
+    let mut state = self.sim.snapshot().clone();
+    state.buttons = 0;
+    state.axes.iter_mut().for_each(|x| *x = 0.0);
+    state.povs.iter_mut().for_each(|x| *x = -1);
+
+    for binding in self.held.keys().filter_map(|k| self.bindings.get(k)) {
+      match *binding {
+        KeyBinding::Button(index) if (1..=32).contains(&index) => state.buttons |= 1 << (index - 1),
+        KeyBinding::Button(_) => (),
+        KeyBinding::Axis { axis, value } => if let Some(x) = state.axes.get_mut(axis) {
+          *x = (*x + value).clamp(-1.0, 1.0);
+        },
+        KeyBinding::Pov { pov, angle } => if let Some(x) = state.povs.get_mut(pov) {
+          *x = angle;
+        }
+      }
+    }
+
+    if &state != self.sim.snapshot() {
+      self.sim.set(state);
+    }
+  }
+
+  /// Read keys from the terminal and drive the joystick until Escape or Ctrl-C is pressed. The terminal is put in
+  /// raw mode while this is running.
+  #[cfg(feature = "keyboard")]
+  pub async fn run(mut self) -> anyhow::Result<()> {
+    crossterm::terminal::enable_raw_mode()?;
+    let result = self.run_raw().await;
+    crossterm::terminal::disable_raw_mode()?;
+    result
+  }
+
+  #[cfg(feature = "keyboard")]
+  async fn run_raw(&mut self) -> anyhow::Result<()> {
+    use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+
+    loop {
+      let time = crate::time::now();
+      while event::poll(std::time::Duration::ZERO)? {
+        if let Event::Key(key) = event::read()? {
+          let mapped = match key.code {
+            KeyCode::Esc => return Ok(()),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+            KeyCode::Char(c) => Key::Char(c.to_ascii_lowercase()),
+            KeyCode::Up => Key::Up,
+            KeyCode::Down => Key::Down,
+            KeyCode::Left => Key::Left,
+            KeyCode::Right => Key::Right,
+            _ => continue
+          };
+          match key.kind {
+            KeyEventKind::Release => self.key_up(mapped),
+            _ => self.key_down(mapped, time)
+          }
+        }
+      }
+      self.update(time);
+      tokio::time::sleep(tokio::time::Duration::from_millis(20)).await;
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::{KeyboardJoystick, Key};
+
+  #[test]
+  fn test_keyboard() {
+    let mut kb = KeyboardJoystick::xbox(0).with_hold_time(0.5);
+    kb.key_down(Key::Char('w'), 0.0);
+    kb.key_down(Key::Char(' '), 0.0);
+    kb.key_down(Key::Right, 0.0);
+    kb.update(0.1);
+    assert_eq!(kb.sim().snapshot().axes[1], -1.0);
+    assert_eq!(kb.sim().snapshot().buttons, 1);
+    assert_eq!(kb.sim().snapshot().povs[0], 90);
+
+    kb.key_down(Key::Char('s'), 0.2);
+    kb.key_up(Key::Char(' '));
+    kb.update(0.3);
+    assert_eq!(kb.sim().snapshot().axes[1], 0.0);
+    assert_eq!(kb.sim().snapshot().buttons, 0);
+
+    kb.update(0.6);
+    assert_eq!(kb.sim().snapshot().axes[1], 1.0);
+    assert_eq!(kb.sim().snapshot().povs[0], -1);
+  }
+}
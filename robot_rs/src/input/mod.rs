@@ -1,2 +1,14 @@
+pub mod events;
+pub mod flight_stick;
+pub mod health;
 pub mod hid;
+pub mod keyboard;
+pub mod profiles;
+pub mod ps4;
+pub mod ps5;
+pub mod recording;
+pub mod rumble;
+pub mod shaping;
+pub mod sim;
+pub mod trigger;
 pub mod xbox;
\ No newline at end of file
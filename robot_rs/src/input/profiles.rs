@@ -0,0 +1,284 @@
+use std::{collections::{BTreeMap, HashMap}, sync::{Arc, RwLock}, fs::File, io::BufReader, path::Path};
+
+use anyhow::anyhow;
+use log::{info, warn};
+use nt4_rs::{nt, types::Value};
+use serde::{Serialize, Deserialize};
+
+use crate::sensors::{digital::DigitalInput, analog::AnalogInput};
+
+use super::{hid::{HID, HIDAxis}, shaping::{ResponseCurve, ShapedAxis}};
+
+// Driver profiles map logical actions (e.g. "intake", "drive_forward") to physical buttons and axes, so
+// different drivers can use different bindings and curves without changing robot code. Profiles are loaded
+// from JSON, e.g.
+//
+// {
+//   "default": "alice",
+//   "profiles": {
+//     "alice": {
+//       "buttons": { "intake": { "port": 0, "button": 1 }, "score": { "port": 0, "pov": { "index": 0, "angle": 90 } } },
+//       "axes": { "drive_forward": { "port": 0, "axis": 1, "deadband": 0.1, "curve": "Squared", "invert": true, "slew_limit": 3.0 } }
+//     }
+//   }
+// }
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ButtonSource {
+  /// A button, where the index starts at 1
+  Button(usize),
+  Pov { index: usize, angle: isize },
+  AxisAbove { axis: usize, threshold: f64 },
+  AxisBelow { axis: usize, threshold: f64 }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ButtonMapping {
+  #[serde(default)]
+  pub port: usize,
+  #[serde(flatten)]
+  pub source: ButtonSource
+}
+
+impl ButtonMapping {
+  pub fn get(&self) -> bool {
+    let hid = HID::new(self.port);
+    match self.source {
+      ButtonSource::Button(index) => hid.button(index).get(),
+      ButtonSource::Pov { index, angle } => hid.pov(index).direction(angle).get(),
+      ButtonSource::AxisAbove { axis, threshold } => hid.axis(axis).above(threshold).get(),
+      ButtonSource::AxisBelow { axis, threshold } => hid.axis(axis).below(threshold).get()
+    }
+  }
+}
+
+fn default_scale() -> f64 { 1.0 }
+fn default_curve() -> ResponseCurve { ResponseCurve::Linear }
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AxisMapping {
+  #[serde(default)]
+  pub port: usize,
+  pub axis: usize,
+  #[serde(default)]
+  pub deadband: f64,
+  #[serde(default = "default_curve")]
+  pub curve: ResponseCurve,
+  #[serde(default = "default_scale")]
+  pub scale: f64,
+  #[serde(default)]
+  pub invert: bool,
+  /// Limit on the rate of change of the output, in units per second
+  #[serde(default)]
+  pub slew_limit: Option<f64>
+}
+
+impl AxisMapping {
+  /// Build the shaped axis described by this mapping
+  pub fn shaped(&self) -> ShapedAxis<HIDAxis> {
+    let shaped = HID::new(self.port).axis(self.axis).shaped()
+      .deadband(self.deadband)
+      .curve(self.curve)
+      .scale(self.scale);
+    let shaped = if self.invert { shaped.invert() } else { shaped };
+    match self.slew_limit {
+      Some(rate) => shaped.slew_limit(rate),
+      None => shaped
+    }
+  }
+
+  /// Shape a raw value, without any slew rate limiting
+  pub fn shape(&self, raw: f64) -> f64 {
+    self.shaped().shape(raw)
+  }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+  #[serde(default)]
+  pub buttons: HashMap<String, ButtonMapping>,
+  #[serde(default)]
+  pub axes: HashMap<String, AxisMapping>
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProfileConfig {
+  pub default: String,
+  pub profiles: BTreeMap<String, Profile>
+}
+
+struct Selected {
+  name: String,
+  profile: Profile,
+  // Kept for as long as the profile is selected, so slew rate limiting has a history to work from
+  axes: HashMap<String, ShapedAxis<HIDAxis>>
+}
+
+impl Selected {
+  fn new(name: String, profile: Profile) -> Self {
+    let axes = profile.axes.iter().map(|(action, mapping)| (action.clone(), mapping.shaped())).collect();
+    Self { name, profile, axes }
+  }
+}
+
+/// The set of available profiles, and the one currently selected. Actions read from the selected profile
+/// every time they're read, so changing profile takes effect immediately.
+pub struct Profiles {
+  profiles: BTreeMap<String, Profile>,
+  selected: Arc<RwLock<Selected>>
+}
+
+impl Profiles {
+  pub fn new(config: ProfileConfig) -> anyhow::Result<Self> {
+    let profile = config.profiles.get(&config.default).cloned()
+      .ok_or_else(|| anyhow!("Default profile \"{}\" does not exist", config.default))?;
+
+    Ok(Self {
+      profiles: config.profiles,
+      selected: Arc::new(RwLock::new(Selected::new(config.default, profile)))
+    })
+  }
+
+  pub fn from_json(json: &str) -> anyhow::Result<Self> {
+    Self::new(serde_json::from_str(json)?)
+  }
+
+  pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+    Self::new(serde_json::from_reader(BufReader::new(File::open(path)?))?)
+  }
+
+  pub fn available(&self) -> Vec<String> {
+    self.profiles.keys().cloned().collect()
+  }
+
+  pub fn selected(&self) -> String {
+    self.selected.read().unwrap().name.clone()
+  }
+
+  pub fn select(&self, name: &str) -> anyhow::Result<()> {
+    let profile = self.profiles.get(name).cloned().ok_or_else(|| anyhow!("No such profile: \"{}\"", name))?;
+    *self.selected.write().unwrap() = Selected::new(name.to_owned(), profile);
+    Ok(())
+  }
+
+  /// A button for the given action. Reads as false if the selected profile doesn't map this action.
+  pub fn button(&self, action: &str) -> ActionButton {
+    ActionButton { action: action.to_owned(), selected: self.selected.clone() }
+  }
+
+  /// An axis for the given action. Reads as 0 if the selected profile doesn't map this action.
+  pub fn axis(&self, action: &str) -> ActionAxis {
+    ActionAxis { action: action.to_owned(), selected: self.selected.clone() }
+  }
+
+  /// Publish the available profiles to `{path}/available`, and switch profiles whenever `{path}/selected` changes.
+  /// Selecting a profile that doesn't exist resets `{path}/selected` to the current profile.
+  pub async fn run_nt(&self, path: &str) {
+    nt!(&format!("{}/available", path), self.available()).unwrap();
+    nt!(&format!("{}/selected", path), self.selected()).unwrap();
+
+    let mut last = self.selected();
+    loop {
+      if let Value::String(name) = nt!(read &format!("{}/selected", path)).data {
+        if name != last {
+          match self.select(&name) {
+            Ok(()) => {
+              info!("Selected driver profile \"{}\"", name);
+              last = name;
+            },
+            Err(e) => {
+              // Put back the profile that's still active, so the dashboard doesn't show the wrong one
+              warn!("{}", e);
+              last = self.selected();
+              nt!(&format!("{}/selected", path), last.clone()).unwrap();
+            }
+          }
+        }
+      }
+      tokio::time::sleep(tokio::time::Duration::from_millis(20)).await;
+    }
+  }
+}
+
+#[derive(Clone)]
+pub struct ActionButton {
+  action: String,
+  selected: Arc<RwLock<Selected>>
+}
+
+impl ActionButton {
+  pub fn mapping(&self) -> Option<ButtonMapping> {
+    self.selected.read().unwrap().profile.buttons.get(&self.action).cloned()
+  }
+}
+
+impl DigitalInput for ActionButton {
+  fn get(&self) -> bool {
+    self.mapping().map(|m| m.get()).unwrap_or(false)
+  }
+}
+
+#[derive(Clone)]
+pub struct ActionAxis {
+  action: String,
+  selected: Arc<RwLock<Selected>>
+}
+
+impl ActionAxis {
+  pub fn mapping(&self) -> Option<AxisMapping> {
+    self.selected.read().unwrap().profile.axes.get(&self.action).cloned()
+  }
+}
+
+impl AnalogInput for ActionAxis {
+  fn get(&self) -> f64 {
+    self.selected.read().unwrap().axes.get(&self.action).map(|a| a.get()).unwrap_or(0.0)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use approx::assert_relative_eq;
+
+  use crate::input::shaping::ResponseCurve;
+
+  use super::{Profiles, ButtonSource};
+
+  const CONFIG: &str = r#"{
+    "default": "alice",
+    "profiles": {
+      "alice": {
+        "buttons": { "intake": { "port": 0, "button": 1 } },
+        "axes": { "drive_forward": { "axis": 1, "deadband": 0.1, "curve": "Squared", "invert": true } }
+      },
+      "bob": {
+        "buttons": { "intake": { "port": 1, "pov": { "index": 0, "angle": 90 } } },
+        "axes": { "drive_forward": { "axis": 5, "curve": { "Expo": 0.5 }, "slew_limit": 2.0 } }
+      }
+    }
+  }"#;
+
+  #[test]
+  fn test_profiles() -> anyhow::Result<()> {
+    let profiles = Profiles::from_json(CONFIG)?;
+    assert_eq!(profiles.available(), vec!["alice", "bob"]);
+
+    let intake = profiles.button("intake");
+    let drive = profiles.axis("drive_forward");
+    assert_eq!(intake.mapping().unwrap().source, ButtonSource::Button(1));
+    assert_relative_eq!(drive.mapping().unwrap().shape(0.55), -0.25);
+    assert!(profiles.axis("climb").mapping().is_none());
+
+    profiles.select("bob")?;
+    assert_eq!(profiles.selected(), "bob");
+    assert_eq!(intake.mapping().unwrap().port, 1);
+    assert_eq!(intake.mapping().unwrap().source, ButtonSource::Pov { index: 0, angle: 90 });
+    assert_eq!(drive.mapping().unwrap().curve, ResponseCurve::Expo(0.5));
+    assert_eq!(drive.mapping().unwrap().slew_limit, Some(2.0));
+
+    assert!(profiles.select("carol").is_err());
+    assert_eq!(profiles.selected(), "bob");
+    Ok(())
+  }
+}
@@ -0,0 +1,45 @@
+use crate::macros::wrapped_traits_nogen;
+
+use super::hid::{HID, HIDAxis, HIDPOV, HIDButton, ControllerKind};
+
+pub struct PS5Controller(HID);
+
+impl From<HID> for PS5Controller {
+  fn from(value: HID) -> Self {
+    Self(value)
+  }
+}
+
+impl PS5Controller {
+  pub fn new(port: usize) -> Self {
+    let hid = HID::new(port);
+    hid.expect_mapping(ControllerKind::PS5);
+    Self(hid)
+  }
+
+  pub fn left_x(&self) -> HIDAxis { self.axis(0) }
+  pub fn left_y(&self) -> HIDAxis { self.axis(1) }
+  pub fn right_x(&self) -> HIDAxis { self.axis(2) }
+  pub fn l2_axis(&self) -> HIDAxis { self.axis(3) }
+  pub fn r2_axis(&self) -> HIDAxis { self.axis(4) }
+  pub fn right_y(&self) -> HIDAxis { self.axis(5) }
+
+  pub fn dpad(&self) -> HIDPOV { self.pov(0) }
+
+  pub fn square(&self) -> HIDButton { self.button(1) }
+  pub fn cross(&self) -> HIDButton { self.button(2) }
+  pub fn circle(&self) -> HIDButton { self.button(3) }
+  pub fn triangle(&self) -> HIDButton { self.button(4) }
+  pub fn l1(&self) -> HIDButton { self.button(5) }
+  pub fn r1(&self) -> HIDButton { self.button(6) }
+  pub fn l2(&self) -> HIDButton { self.button(7) }
+  pub fn r2(&self) -> HIDButton { self.button(8) }
+  pub fn create(&self) -> HIDButton { self.button(9) }
+  pub fn options(&self) -> HIDButton { self.button(10) }
+  pub fn l3(&self) -> HIDButton { self.button(11) }
+  pub fn r3(&self) -> HIDButton { self.button(12) }
+  pub fn ps(&self) -> HIDButton { self.button(13) }
+  pub fn touchpad(&self) -> HIDButton { self.button(14) }
+}
+
+wrapped_traits_nogen!(PS5Controller, HID);
@@ -0,0 +1,238 @@
+use std::{io::{Write, BufRead, BufReader, BufWriter}, fs::File, path::Path};
+
+use serde::{Serialize, Deserialize};
+
+use crate::time::now;
+
+use super::{events::HIDSnapshot, hid::{HID, HIDType}, sim};
+
+/// The descriptor of a joystick, which identifies the kind of controller and whether it's connected
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JoystickDescriptor {
+  pub name: String,
+  pub hid_type: HIDType,
+  pub is_xbox: bool,
+  pub n_buttons: usize,
+  pub n_axes: usize,
+  pub n_povs: usize
+}
+
+impl JoystickDescriptor {
+  pub fn capture(port: usize) -> Self {
+    let hid = HID::new(port);
+    Self {
+      name: hid.name(),
+      hid_type: hid.hid_type(),
+      is_xbox: hid.is_xbox(),
+      n_buttons: hid.n_buttons(),
+      n_axes: hid.n_axes(),
+      n_povs: hid.n_pov()
+    }
+  }
+
+  /// Set this descriptor on a simulated joystick
+  pub fn apply(&self, port: usize) {
+    sim::set_descriptor(port, &self.name, self.hid_type, self.is_xbox, self.n_buttons, self.n_axes, self.n_povs);
+  }
+}
+
+/// The state of all recorded joysticks at a point in time, relative to the start of the recording. Descriptors are
+/// only included in the first frame, and whenever they change (e.g. a controller is plugged in).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InputFrame {
+  pub time: f64,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub descriptors: Vec<(usize, JoystickDescriptor)>,
+  pub joysticks: Vec<(usize, HIDSnapshot)>
+}
+
+/// Records driver inputs to a file (or any other writer), one JSON frame per line.
+pub struct InputRecorder<W: Write> {
+  writer: W,
+  ports: Vec<usize>,
+  start: Option<f64>,
+  last_descriptors: Vec<Option<JoystickDescriptor>>
+}
+
+impl InputRecorder<BufWriter<File>> {
+  pub fn create<P: AsRef<Path>>(path: P, ports: Vec<usize>) -> anyhow::Result<Self> {
+    Ok(Self::new(BufWriter::new(File::create(path)?), ports))
+  }
+}
+
+impl<W: Write> InputRecorder<W> {
+  pub fn new(writer: W, ports: Vec<usize>) -> Self {
+    let last_descriptors = vec![None; ports.len()];
+    Self { writer, ports, start: None, last_descriptors }
+  }
+
+  /// Capture and record the current state of all joysticks
+  pub fn record(&mut self) -> anyhow::Result<()> {
+    let time = now();
+    let start = *self.start.get_or_insert(time);
+
+    let mut descriptors = vec![];
+    for (port, last) in self.ports.iter().zip(self.last_descriptors.iter_mut()) {
+      let descriptor = JoystickDescriptor::capture(*port);
+      if last.as_ref() != Some(&descriptor) {
+        descriptors.push((*port, descriptor.clone()));
+        *last = Some(descriptor);
+      }
+    }
+
+    let frame = InputFrame {
+      time: time - start,
+      descriptors,
+      joysticks: self.ports.iter().map(|port| (*port, HIDSnapshot::capture(*port))).collect()
+    };
+    self.record_frame(&frame)
+  }
+
+  pub fn record_frame(&mut self, frame: &InputFrame) -> anyhow::Result<()> {
+    serde_json::to_writer(&mut self.writer, frame)?;
+    self.writer.write_all(b"\n")?;
+    Ok(())
+  }
+
+  pub fn flush(&mut self) -> anyhow::Result<()> {
+    Ok(self.writer.flush()?)
+  }
+
+  /// Record every 20ms, until an error occurs
+  pub async fn run(mut self) -> anyhow::Result<()> {
+    loop {
+      self.record()?;
+      tokio::time::sleep(tokio::time::Duration::from_millis(20)).await;
+    }
+  }
+}
+
+/// Replays a recording into the simulated Driver Station, so teleop code can be run against a real driving session.
+pub struct InputReplay {
+  frames: Vec<InputFrame>,
+  next: usize,
+  start: Option<f64>
+}
+
+impl InputReplay {
+  pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+    Self::from_reader(BufReader::new(File::open(path)?))
+  }
+
+  pub fn from_reader<R: BufRead>(reader: R) -> anyhow::Result<Self> {
+    let mut frames = vec![];
+    for line in reader.lines() {
+      let line = line?;
+      if !line.trim().is_empty() {
+        frames.push(serde_json::from_str(&line)?);
+      }
+    }
+    Ok(Self::new(frames))
+  }
+
+  pub fn new(frames: Vec<InputFrame>) -> Self {
+    Self { frames, next: 0, start: None }
+  }
+
+  pub fn is_finished(&self) -> bool {
+    self.next >= self.frames.len()
+  }
+
+  pub fn duration(&self) -> f64 {
+    self.frames.last().map(|x| x.time).unwrap_or(0.0)
+  }
+
+  /// Get the frames that are due at the given time since the start of the replay, marking them as replayed.
+  pub fn due_frames(&mut self, elapsed: f64) -> &[InputFrame] {
+    let from = self.next;
+    while self.next < self.frames.len() && self.frames[self.next].time <= elapsed {
+      self.next += 1;
+    }
+    &self.frames[from..self.next]
+  }
+
+  /// Push any frames that are now due into the simulated Driver Station. The replay starts
+  /// on the first call to update. Returns false once the replay has finished.
+  pub fn update(&mut self) -> bool {
+    let time = now();
+    let elapsed = time - *self.start.get_or_insert(time);
+
+    // Only the latest frame for each joystick matters, but applying them in order is simplest
+    let mut updated = false;
+    for frame in self.due_frames(elapsed) {
+      for (port, descriptor) in frame.descriptors.iter() {
+        descriptor.apply(*port);
+      }
+      for (port, snapshot) in frame.joysticks.iter() {
+        sim::set_snapshot(*port, snapshot);
+      }
+      updated = true;
+    }
+
+    if updated {
+      sim::notify_new_data();
+    }
+    !self.is_finished()
+  }
+
+  /// Run the replay to completion, updating every 20ms
+  pub async fn run(mut self) {
+    while self.update() {
+      tokio::time::sleep(tokio::time::Duration::from_millis(20)).await;
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use crate::{input::{events::HIDSnapshot, hid::{HID, HIDType, ControllerKind}}, sensors::digital::DigitalInput};
+
+  use super::{InputFrame, InputRecorder, InputReplay, JoystickDescriptor};
+
+  fn xbox() -> JoystickDescriptor {
+    JoystickDescriptor { name: "Xbox Controller".to_owned(), hid_type: HIDType::XInputGamepad, is_xbox: true, n_buttons: 4, n_axes: 2, n_povs: 1 }
+  }
+
+  fn frame(time: f64, buttons: u32) -> InputFrame {
+    InputFrame {
+      time,
+      descriptors: if time == 0.0 { vec![(0, xbox())] } else { vec![] },
+      joysticks: vec![(0, HIDSnapshot { buttons, button_count: 4, axes: vec![0.0, 0.5], povs: vec![-1] })]
+    }
+  }
+
+  #[test]
+  fn test_record_replay() -> anyhow::Result<()> {
+    let frames = [ frame(0.0, 0), frame(0.02, 1), frame(0.04, 3) ];
+
+    let mut buf = vec![];
+    let mut recorder = InputRecorder::new(&mut buf, vec![0]);
+    for f in frames.iter() {
+      recorder.record_frame(f)?;
+    }
+
+    let mut replay = InputReplay::from_reader(&buf[..])?;
+    assert_eq!(replay.duration(), 0.04);
+    assert_eq!(replay.due_frames(0.01), &frames[0..1]);
+    assert!(replay.due_frames(0.015).is_empty());
+    assert_eq!(replay.due_frames(1.0), &frames[1..3]);
+    assert!(replay.is_finished());
+    Ok(())
+  }
+
+  #[test]
+  fn test_replay_into_sim() {
+    let mut replay = InputReplay::new(vec![
+      InputFrame { time: 0.0, descriptors: vec![(1, xbox())], joysticks: vec![(1, HIDSnapshot { buttons: 0b10, button_count: 4, axes: vec![0.0, 0.5], povs: vec![90] })] }
+    ]);
+    assert!(!replay.update());
+    // Pick up the new data, as the robot loop would
+    unsafe { wpilib_hal::HAL_RefreshDSData() };
+
+    let hid = HID::new(1);
+    assert!(hid.is_connected());
+    assert_eq!(hid.kind(), ControllerKind::Xbox);
+    assert!(hid.button(2).get());
+    assert_eq!(hid.pov(0).get(), 90);
+  }
+}
@@ -0,0 +1,87 @@
+use tokio::time::{sleep, Duration};
+
+use super::hid::HID;
+
+#[derive(Debug, Clone, Copy)]
+pub enum RumblePattern {
+  /// A single rumble of the given intensity (0..1) and duration (seconds)
+  Pulse { intensity: f64, duration: f64 },
+  /// Two short rumbles in quick succession
+  DoubleTap { intensity: f64 },
+  /// Rumble that changes linearly in intensity over the given duration (seconds)
+  Fade { from: f64, to: f64, duration: f64 }
+}
+
+// Negative, NaN and infinite durations are treated as zero
+fn seconds(duration: f64) -> Duration {
+  if duration.is_finite() && duration > 0.0 {
+    Duration::from_secs_f64(duration)
+  } else {
+    Duration::ZERO
+  }
+}
+
+impl RumblePattern {
+  /// The intensities making up this pattern, each held for the given duration
+  pub fn steps(&self) -> Vec<(f64, Duration)> {
+    match *self {
+      RumblePattern::Pulse { intensity, duration } => vec![(intensity, seconds(duration))],
+      RumblePattern::DoubleTap { intensity } => {
+        let tap = Duration::from_millis(100);
+        vec![(intensity, tap), (0.0, tap), (intensity, tap)]
+      },
+      RumblePattern::Fade { from, to, duration } => {
+        // Roughly 20ms per step, with the final intensity reached at the end of the duration
+        let duration = seconds(duration);
+        let steps = (duration.as_secs_f64() / 0.02).ceil().max(1.0) as u32;
+        (0..=steps).map(|i| {
+          let step = if i < steps { duration / steps } else { Duration::ZERO };
+          (from + (to - from) * (i as f64 / steps as f64), step)
+        }).collect()
+      }
+    }
+  }
+}
+
+impl HID {
+  /// Play a rumble pattern on this controller. The rumble is stopped once the pattern completes, including any
+  /// other rumble (manual or from another pattern) on the same port.
+  pub async fn rumble(&self, pattern: RumblePattern) {
+    for (intensity, duration) in pattern.steps() {
+      self.set_rumble(intensity, intensity);
+      sleep(duration).await;
+    }
+    self.set_rumble(0.0, 0.0);
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use tokio::time::Duration;
+
+  use super::RumblePattern;
+
+  #[test]
+  fn test_pulse() {
+    assert_eq!(RumblePattern::Pulse { intensity: 0.5, duration: 0.25 }.steps(), vec![(0.5, Duration::from_millis(250))]);
+    assert_eq!(RumblePattern::Pulse { intensity: 0.5, duration: -1.0 }.steps(), vec![(0.5, Duration::ZERO)]);
+    assert_eq!(RumblePattern::Pulse { intensity: 0.5, duration: f64::NAN }.steps(), vec![(0.5, Duration::ZERO)]);
+  }
+
+  #[test]
+  fn test_double_tap() {
+    let steps = RumblePattern::DoubleTap { intensity: 1.0 }.steps();
+    assert_eq!(steps.iter().map(|s| s.0).collect::<Vec<_>>(), vec![1.0, 0.0, 1.0]);
+  }
+
+  #[test]
+  fn test_fade() {
+    let steps = RumblePattern::Fade { from: 0.0, to: 1.0, duration: 0.1 }.steps();
+    assert_eq!(steps.len(), 6);
+    assert_eq!(steps.first().unwrap().0, 0.0);
+    assert_eq!(steps.last().unwrap().0, 1.0);
+    assert_eq!(steps.iter().map(|s| s.1).sum::<Duration>(), Duration::from_millis(100));
+
+    assert_eq!(RumblePattern::Fade { from: 0.0, to: 1.0, duration: f64::INFINITY }.steps().len(), 2);
+  }
+}
@@ -0,0 +1,207 @@
+use std::sync::Mutex;
+
+use serde::{Serialize, Deserialize};
+
+use crate::{sensors::analog::AnalogInput, control::slew_rate::SlewRateLimiter, time::now};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ResponseCurve {
+  Linear,
+  Squared,
+  Cubic,
+  /// Blend between linear (0.0) and cubic (1.0) response
+  Expo(f64)
+}
+
+impl ResponseCurve {
+  pub fn apply(&self, value: f64) -> f64 {
+    match self {
+      ResponseCurve::Linear => value,
+      ResponseCurve::Squared => value * value.abs(),
+      ResponseCurve::Cubic => value * value * value,
+      ResponseCurve::Expo(k) => k * value * value * value + (1.0 - k) * value
+    }
+  }
+}
+
+/// Zero any value within the deadband, and rescale the rest so the output still spans the full range
+/// (i.e. the output starts from 0 at the edge of the deadband, instead of jumping).
+pub fn apply_deadband(value: f64, deadband: f64) -> f64 {
+  if value.abs() < deadband {
+    0.0
+  } else {
+    value.signum() * (value.abs() - deadband) / (1.0 - deadband)
+  }
+}
+
+/// An axis with deadband, response curve, scaling, inversion and slew rate limiting applied, in that order.
+pub struct ShapedAxis<A: AnalogInput> {
+  axis: A,
+  deadband: f64,
+  curve: ResponseCurve,
+  scale: f64,
+  inverted: bool,
+  slew: Option<Mutex<SlewRateLimiter>>
+}
+
+impl<A: AnalogInput> ShapedAxis<A> {
+  pub fn new(axis: A) -> Self {
+    Self {
+      axis,
+      deadband: 0.0,
+      curve: ResponseCurve::Linear,
+      scale: 1.0,
+      inverted: false,
+      slew: None
+    }
+  }
+
+  pub fn deadband(mut self, deadband: f64) -> Self {
+    self.deadband = deadband;
+    self
+  }
+
+  pub fn curve(mut self, curve: ResponseCurve) -> Self {
+    self.curve = curve;
+    self
+  }
+
+  pub fn scale(mut self, scale: f64) -> Self {
+    self.scale = scale;
+    self
+  }
+
+  pub fn invert(mut self) -> Self {
+    self.inverted = !self.inverted;
+    self
+  }
+
+  /// Limit the rate of change of the output, in units per second
+  pub fn slew_limit(mut self, rate: f64) -> Self {
+    self.slew = Some(Mutex::new(SlewRateLimiter::new(rate)));
+    self
+  }
+
+  /// Shape a raw value, without any slew rate limiting
+  pub fn shape(&self, raw: f64) -> f64 {
+    let value = self.curve.apply(apply_deadband(raw, self.deadband)) * self.scale;
+    if self.inverted { -value } else { value }
+  }
+}
+
+impl<A: AnalogInput> AnalogInput for ShapedAxis<A> {
+  fn get(&self) -> f64 {
+    let value = self.shape(self.axis.get());
+    match &self.slew {
+      Some(slew) => slew.lock().unwrap().calculate(value, now()),
+      None => value
+    }
+  }
+}
+
+/// A pair of axes making up a stick, with a radial (circular) deadband. A radial deadband avoids the "snapping"
+/// to the X and Y axes that you get with individual deadbands on each axis.
+pub struct Stick<X: AnalogInput, Y: AnalogInput> {
+  x: X,
+  y: Y,
+  deadband: f64,
+  curve: ResponseCurve
+}
+
+impl<X: AnalogInput, Y: AnalogInput> Stick<X, Y> {
+  pub fn new(x: X, y: Y) -> Self {
+    Self { x, y, deadband: 0.0, curve: ResponseCurve::Linear }
+  }
+
+  pub fn deadband(mut self, deadband: f64) -> Self {
+    self.deadband = deadband;
+    self
+  }
+
+  /// Response curve, applied to the magnitude of the stick
+  pub fn curve(mut self, curve: ResponseCurve) -> Self {
+    self.curve = curve;
+    self
+  }
+
+  pub fn shape(&self, x: f64, y: f64) -> (f64, f64) {
+    let magnitude = x.hypot(y);
+    if magnitude < self.deadband || magnitude == 0.0 {
+      return (0.0, 0.0)
+    }
+
+    let shaped = self.curve.apply(apply_deadband(magnitude.min(1.0), self.deadband));
+    (x / magnitude * shaped, y / magnitude * shaped)
+  }
+
+  pub fn get(&self) -> (f64, f64) {
+    self.shape(self.x.get(), self.y.get())
+  }
+
+  pub fn x(&self) -> StickAxis<'_, X, Y> {
+    StickAxis { stick: self, is_x: true }
+  }
+
+  pub fn y(&self) -> StickAxis<'_, X, Y> {
+    StickAxis { stick: self, is_x: false }
+  }
+}
+
+pub struct StickAxis<'a, X: AnalogInput, Y: AnalogInput> {
+  stick: &'a Stick<X, Y>,
+  is_x: bool
+}
+
+impl<'a, X: AnalogInput, Y: AnalogInput> AnalogInput for StickAxis<'a, X, Y> {
+  fn get(&self) -> f64 {
+    let (x, y) = self.stick.get();
+    if self.is_x { x } else { y }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use approx::assert_relative_eq;
+
+  use crate::sensors::analog::AnalogInput;
+
+  use super::{apply_deadband, ResponseCurve, ShapedAxis, Stick};
+
+  struct Constant(f64);
+  impl AnalogInput for Constant {
+    fn get(&self) -> f64 { self.0 }
+  }
+
+  #[test]
+  fn test_deadband() {
+    assert_relative_eq!(apply_deadband(0.05, 0.1), 0.0);
+    assert_relative_eq!(apply_deadband(-0.05, 0.1), 0.0);
+    assert_relative_eq!(apply_deadband(0.1, 0.1), 0.0);
+    assert_relative_eq!(apply_deadband(0.55, 0.1), 0.5);
+    assert_relative_eq!(apply_deadband(-1.0, 0.1), -1.0);
+  }
+
+  #[test]
+  fn test_curves() {
+    assert_relative_eq!(ResponseCurve::Squared.apply(-0.5), -0.25);
+    assert_relative_eq!(ResponseCurve::Cubic.apply(-0.5), -0.125);
+    assert_relative_eq!(ResponseCurve::Expo(0.5).apply(0.5), 0.3125);
+    assert_relative_eq!(ResponseCurve::Expo(0.5).apply(1.0), 1.0);
+  }
+
+  #[test]
+  fn test_shaped_axis() {
+    let axis = ShapedAxis::new(Constant(0.55)).deadband(0.1).curve(ResponseCurve::Squared).scale(0.5).invert();
+    assert_relative_eq!(axis.get(), -0.125);
+  }
+
+  #[test]
+  fn test_radial_deadband() {
+    let stick = Stick::new(Constant(0.06), Constant(0.08)).deadband(0.2);
+    assert_eq!(stick.get(), (0.0, 0.0));
+
+    let stick = Stick::new(Constant(0.3), Constant(0.4)).deadband(0.2);
+    assert_relative_eq!(stick.x().get(), 0.225);
+    assert_relative_eq!(stick.y().get(), 0.3);
+  }
+}
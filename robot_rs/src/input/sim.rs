@@ -0,0 +1,130 @@
+use std::ffi::c_char;
+
+use wpilib_hal::{HAL_JoystickButtons, HAL_JoystickAxes, HAL_JoystickPOVs, HAL_JoystickDescriptor, HALSIM_SetJoystickButtons, HALSIM_SetJoystickAxes, HALSIM_SetJoystickPOVs, HALSIM_SetJoystickDescriptor, HALSIM_NotifyDriverStationNewData};
+
+use super::{events::HIDSnapshot, hid::HIDType};
+
+// Simulated joysticks, through the HAL Driver Station simulation API. These functions
+// have no effect when running on a real robot.
+
+/// Set the full state of a simulated joystick from a snapshot
+pub fn set_snapshot(port: usize, snapshot: &HIDSnapshot) {
+  let buttons = HAL_JoystickButtons {
+    buttons: snapshot.buttons,
+    count: snapshot.button_count as u8
+  };
+
+  let mut axes = HAL_JoystickAxes::default();
+  for (out, value) in axes.axes.iter_mut().zip(snapshot.axes.iter()) {
+    *out = *value as f32;
+  }
+  axes.count = snapshot.axes.len().min(axes.axes.len()) as i16;
+
+  let mut povs = HAL_JoystickPOVs::default();
+  for (out, value) in povs.povs.iter_mut().zip(snapshot.povs.iter()) {
+    *out = *value as i16;
+  }
+  povs.count = snapshot.povs.len().min(povs.povs.len()) as i16;
+
+  unsafe {
+    HALSIM_SetJoystickButtons(port as i32, &buttons);
+    HALSIM_SetJoystickAxes(port as i32, &axes);
+    HALSIM_SetJoystickPOVs(port as i32, &povs);
+  }
+}
+
+/// Notify the simulated Driver Station that new data is available, so it's picked up on the next refresh
+pub fn notify_new_data() {
+  unsafe { HALSIM_NotifyDriverStationNewData() }
+}
+
+/// Set the descriptor (name, type and layout) of a simulated joystick
+pub fn set_descriptor(port: usize, name: &str, hid_type: HIDType, is_xbox: bool, n_buttons: usize, n_axes: usize, n_povs: usize) {
+  let mut descriptor = HAL_JoystickDescriptor {
+    isXbox: is_xbox as u8,
+    type_: hid_type.into(),
+    buttonCount: n_buttons as u8,
+    axisCount: n_axes as u8,
+    povCount: n_povs as u8,
+    ..Default::default()
+  };
+  // Leave room for the null terminator
+  let max_len = descriptor.name.len() - 1;
+  for (out, c) in descriptor.name.iter_mut().zip(name.bytes().take(max_len)) {
+    *out = c as c_char;
+  }
+
+  unsafe { HALSIM_SetJoystickDescriptor(port as i32, &descriptor) };
+}
+
+/// A simulated joystick, which can be driven from code (e.g. tests, or the keyboard). Every change is pushed
+/// to the simulated Driver Station immediately, and read back by the HID on the same port.
+pub struct SimHID {
+  port: usize,
+  state: HIDSnapshot
+}
+
+impl SimHID {
+  pub fn new(port: usize, name: &str, hid_type: HIDType, is_xbox: bool, n_buttons: usize, n_axes: usize, n_povs: usize) -> Self {
+    set_descriptor(port, name, hid_type, is_xbox, n_buttons, n_axes, n_povs);
+    let sim = Self {
+      port,
+      state: HIDSnapshot { buttons: 0, button_count: n_buttons, axes: vec![0.0; n_axes], povs: vec![-1; n_povs] }
+    };
+    sim.flush();
+    sim
+  }
+
+  /// A simulated Xbox controller, with the same layout as the Xbox mapping
+  pub fn xbox(port: usize) -> Self {
+    Self::new(port, "Xbox Controller", HIDType::XInputGamepad, true, 10, 6, 1)
+  }
+
+  /// A simulated PS4 controller, with the same layout as the PS4Controller mapping
+  pub fn ps4(port: usize) -> Self {
+    Self::new(port, "Wireless Controller", HIDType::HIDGamepad, false, 14, 6, 1)
+  }
+
+  /// A simulated flight stick, with the same layout as the FlightStick mapping
+  pub fn flight_stick(port: usize) -> Self {
+    Self::new(port, "Logitech Extreme 3D", HIDType::HIDFlight, false, 12, 4, 1)
+  }
+
+  pub fn port(&self) -> usize { self.port }
+
+  pub fn snapshot(&self) -> &HIDSnapshot { &self.state }
+
+  /// Set a button, where index starts at 1
+  pub fn set_button(&mut self, index: usize, value: bool) {
+    if !(1..=32).contains(&index) { return }
+    let mask = 1 << (index - 1);
+    self.state.buttons = if value { self.state.buttons | mask } else { self.state.buttons & !mask };
+    self.flush();
+  }
+
+  pub fn set_axis(&mut self, index: usize, value: f64) {
+    if let Some(axis) = self.state.axes.get_mut(index) {
+      *axis = value.clamp(-1.0, 1.0);
+      self.flush();
+    }
+  }
+
+  /// Set a POV angle in degrees (0 = up, 90 = right), or -1 if not pressed
+  pub fn set_pov(&mut self, index: usize, angle: isize) {
+    if let Some(pov) = self.state.povs.get_mut(index) {
+      *pov = angle;
+      self.flush();
+    }
+  }
+
+  /// Set the entire state of the joystick at once
+  pub fn set(&mut self, state: HIDSnapshot) {
+    self.state = state;
+    self.flush();
+  }
+
+  fn flush(&self) {
+    set_snapshot(self.port, &self.state);
+    notify_new_data();
+  }
+}
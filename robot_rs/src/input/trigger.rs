@@ -0,0 +1,236 @@
+use std::sync::Mutex;
+
+use crate::{sensors::{digital::{DigitalInput, InvertInput}, analog::AnalogInput}, control::edge_detect::{Edge, EdgeDetectorOwned}, time::now};
+
+use super::hid::HIDPOV;
+
+// Triggers are DigitalInputs built from other inputs. Stateful triggers (Toggle, HeldFor)
+// update their state when they're read, so should be polled regularly - e.g. through an EdgeDetector.
+
+pub struct And<A: DigitalInput, B: DigitalInput>(pub A, pub B);
+
+impl<A: DigitalInput, B: DigitalInput> DigitalInput for And<A, B> {
+  fn get(&self) -> bool {
+    self.0.get() && self.1.get()
+  }
+}
+
+pub struct Or<A: DigitalInput, B: DigitalInput>(pub A, pub B);
+
+impl<A: DigitalInput, B: DigitalInput> DigitalInput for Or<A, B> {
+  fn get(&self) -> bool {
+    self.0.get() || self.1.get()
+  }
+}
+
+/// An analog input (e.g. a trigger axis) used as a button, active when past a threshold.
+pub struct AxisButton<A: AnalogInput> {
+  axis: A,
+  threshold: f64,
+  above: bool
+}
+
+impl<A: AnalogInput> AxisButton<A> {
+  /// Active when the axis is greater than the threshold
+  pub fn above(axis: A, threshold: f64) -> Self {
+    Self { axis, threshold, above: true }
+  }
+
+  /// Active when the axis is less than the threshold
+  pub fn below(axis: A, threshold: f64) -> Self {
+    Self { axis, threshold, above: false }
+  }
+}
+
+impl<A: AnalogInput> DigitalInput for AxisButton<A> {
+  fn get(&self) -> bool {
+    let value = self.axis.get();
+    if self.above { value > self.threshold } else { value < self.threshold }
+  }
+}
+
+/// A single direction of a POV hat, used as a button.
+pub struct POVButton {
+  pov: HIDPOV,
+  angle: isize
+}
+
+impl POVButton {
+  pub fn new(pov: HIDPOV, angle: isize) -> Self {
+    Self { pov, angle }
+  }
+}
+
+impl DigitalInput for POVButton {
+  fn get(&self) -> bool {
+    self.pov.get() == self.angle
+  }
+}
+
+/// Flips state every time the input is pressed.
+pub struct Toggle<D: DigitalInput> {
+  input: D,
+  // (last input value, toggled state)
+  state: Mutex<(bool, bool)>
+}
+
+impl<D: DigitalInput> Toggle<D> {
+  pub fn new(input: D) -> Self {
+    let last = input.get();
+    Self { input, state: Mutex::new((last, false)) }
+  }
+
+  pub fn set(&self, value: bool) {
+    self.state.lock().unwrap().1 = value;
+  }
+}
+
+impl<D: DigitalInput> DigitalInput for Toggle<D> {
+  fn get(&self) -> bool {
+    let value = self.input.get();
+    let mut state = self.state.lock().unwrap();
+    if value && !state.0 {
+      state.1 = !state.1;
+    }
+    state.0 = value;
+    state.1
+  }
+}
+
+/// Active once the input has been held for a given duration (in seconds).
+pub struct HeldFor<D: DigitalInput> {
+  input: D,
+  duration: f64,
+  since: Mutex<Option<f64>>
+}
+
+impl<D: DigitalInput> HeldFor<D> {
+  pub fn new(input: D, duration: f64) -> Self {
+    Self { input, duration, since: Mutex::new(None) }
+  }
+
+  pub fn get_at(&self, time: f64) -> bool {
+    let mut since = self.since.lock().unwrap();
+    if self.input.get() {
+      time - *since.get_or_insert(time) >= self.duration
+    } else {
+      *since = None;
+      false
+    }
+  }
+}
+
+impl<D: DigitalInput> DigitalInput for HeldFor<D> {
+  fn get(&self) -> bool {
+    self.get_at(now())
+  }
+}
+
+pub trait TriggerExt : DigitalInput + Sized {
+  fn and<B: DigitalInput>(self, other: B) -> And<Self, B> {
+    And(self, other)
+  }
+
+  fn or<B: DigitalInput>(self, other: B) -> Or<Self, B> {
+    Or(self, other)
+  }
+
+  fn not(self) -> InvertInput<Self> {
+    InvertInput(self)
+  }
+
+  fn toggled(self) -> Toggle<Self> {
+    Toggle::new(self)
+  }
+
+  fn held_for(self, duration: f64) -> HeldFor<Self> {
+    HeldFor::new(self, duration)
+  }
+
+  fn edge_take(self, edge: Edge) -> EdgeDetectorOwned<Self> {
+    EdgeDetectorOwned::new(self, edge)
+  }
+}
+
+impl<D: DigitalInput> TriggerExt for D {}
+
+#[cfg(test)]
+mod test {
+  use std::sync::{Arc, atomic::{AtomicBool, Ordering}};
+
+  use crate::{sensors::{digital::DigitalInput, analog::AnalogInput}, control::edge_detect::Edge};
+
+  use super::{TriggerExt, AxisButton};
+
+  #[derive(Clone)]
+  struct TestButton(Arc<AtomicBool>);
+  impl TestButton {
+    fn new(value: bool) -> Self { Self(Arc::new(AtomicBool::new(value))) }
+    fn set(&self, value: bool) { self.0.store(value, Ordering::Relaxed) }
+  }
+  impl DigitalInput for TestButton {
+    fn get(&self) -> bool { self.0.load(Ordering::Relaxed) }
+  }
+
+  struct Constant(f64);
+  impl AnalogInput for Constant {
+    fn get(&self) -> f64 { self.0 }
+  }
+
+  #[test]
+  fn test_logic() {
+    let a = TestButton::new(true);
+    let b = TestButton::new(false);
+    assert!(!a.clone().and(b.clone()).get());
+    assert!(a.clone().or(b.clone()).get());
+    assert!(a.clone().and(b.clone().not()).get());
+  }
+
+  #[test]
+  fn test_axis_button() {
+    assert!(AxisButton::above(Constant(0.6), 0.5).get());
+    assert!(!AxisButton::above(Constant(0.4), 0.5).get());
+    assert!(AxisButton::below(Constant(-0.6), -0.5).get());
+  }
+
+  #[test]
+  fn test_toggle() {
+    let button = TestButton::new(false);
+    let toggle = button.clone().toggled();
+    assert!(!toggle.get());
+    button.set(true);
+    assert!(toggle.get());
+    assert!(toggle.get());
+    button.set(false);
+    assert!(toggle.get());
+    button.set(true);
+    assert!(!toggle.get());
+  }
+
+  #[test]
+  fn test_held_for() {
+    let button = TestButton::new(false);
+    let held = button.clone().held_for(0.5);
+    assert!(!held.get_at(0.0));
+    button.set(true);
+    assert!(!held.get_at(1.0));
+    assert!(!held.get_at(1.4));
+    assert!(held.get_at(1.5));
+    button.set(false);
+    assert!(!held.get_at(1.6));
+    button.set(true);
+    assert!(!held.get_at(1.7));
+  }
+
+  #[test]
+  fn test_edge() {
+    let button = TestButton::new(false);
+    let mut edge = button.clone().toggled().edge_take(Edge::Falling);
+    button.set(true);
+    assert!(!edge.get());
+    button.set(false);
+    assert!(!edge.get());
+    button.set(true);
+    assert!(edge.get());
+  }
+}
@@ -1,6 +1,6 @@
 use crate::macros::wrapped_traits_nogen;
 
-use super::hid::{HID, HIDAxis, HIDPOV, HIDButton};
+use super::hid::{HID, HIDAxis, HIDPOV, HIDButton, ControllerKind};
 
 pub struct Xbox(HID);
 
@@ -12,7 +12,9 @@ impl From<HID> for Xbox {
 
 impl Xbox {
   pub fn new(port: usize) -> Self {
-    Self(HID::new(port))
+    let hid = HID::new(port);
+    hid.expect_mapping(ControllerKind::Xbox);
+    Self(hid)
   }
 
   pub fn left_x(&self) -> HIDAxis { self.axis(0) }
@@ -7,5 +7,6 @@ pub mod start;
 pub mod macros;
 pub mod ds;
 pub mod control;
+pub mod estimation;
 pub mod types;
 pub mod time;
\ No newline at end of file
@@ -1,4 +1,4 @@
-use std::ops::Mul;
+use std::{ops::Mul, f64::consts::PI};
 
 pub struct DcMotor {
   pub v_nom: f64,
@@ -25,6 +25,11 @@ impl DcMotor {
     self.w_free / (self.v_nom - self.R() * self.i_free)
   }
 
+  /// As kw, but in rad/s per volt instead of RPM per volt
+  pub fn kw_rad_per_sec(&self) -> f64 {
+    self.kw() * 2.0 * PI / 60.0
+  }
+
   pub fn kt(&self) -> f64 {
     self.t_stall / self.i_stall
   }
@@ -0,0 +1,101 @@
+use nalgebra::{SMatrix, SVector, DMatrix};
+
+/// A continuous-time linear system, in state-space form:
+///   dx/dt = Ax + Bu
+///   y = Cx + Du
+/// where x is the state (S states), u is the input (I inputs) and y is the output (O outputs).
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinearSystem<const S: usize, const I: usize, const O: usize> {
+  pub a: SMatrix<f64, S, S>,
+  pub b: SMatrix<f64, S, I>,
+  pub c: SMatrix<f64, O, S>,
+  pub d: SMatrix<f64, O, I>
+}
+
+impl<const S: usize, const I: usize, const O: usize> LinearSystem<S, I, O> {
+  pub fn new(a: SMatrix<f64, S, S>, b: SMatrix<f64, S, I>, c: SMatrix<f64, O, S>, d: SMatrix<f64, O, I>) -> Self {
+    Self { a, b, c, d }
+  }
+
+  /// Discretise the system at the given loop period (in seconds), giving the discrete A and B matrices such that
+  /// x[k+1] = A x[k] + B u[k], assuming u is held constant over the period (zero-order hold).
+  pub fn discretize(&self, dt: f64) -> (SMatrix<f64, S, S>, SMatrix<f64, S, I>) {
+    discretize_ab(&self.a, &self.b, dt)
+  }
+
+  /// The state after dt seconds, starting from x with input u
+  pub fn calculate_x(&self, x: &SVector<f64, S>, u: &SVector<f64, I>, dt: f64) -> SVector<f64, S> {
+    let (a, b) = self.discretize(dt);
+    a * x + b * u
+  }
+
+  pub fn calculate_y(&self, x: &SVector<f64, S>, u: &SVector<f64, I>) -> SVector<f64, O> {
+    self.c * x + self.d * u
+  }
+}
+
+/// Discretise continuous A and B matrices with a zero-order hold. Computed with the matrix exponential of the
+/// block matrix [[A, B], [0, 0]] * dt, whose top blocks are the discrete A and B. Unlike A^-1 (e^(A dt) - I) B,
+/// this works when A is singular (as it is for most mechanisms with a position state).
+pub fn discretize_ab<const S: usize, const I: usize>(a: &SMatrix<f64, S, S>, b: &SMatrix<f64, S, I>, dt: f64) -> (SMatrix<f64, S, S>, SMatrix<f64, S, I>) {
+  let mut m = DMatrix::<f64>::zeros(S + I, S + I);
+  m.view_mut((0, 0), (S, S)).copy_from(&(a * dt));
+  m.view_mut((0, S), (S, I)).copy_from(&(b * dt));
+
+  let phi = m.exp();
+  (
+    SMatrix::from_fn(|r, c| phi[(r, c)]),
+    SMatrix::from_fn(|r, c| phi[(r, S + c)])
+  )
+}
+
+/// Discretise a continuous process noise covariance matrix Q for the system matrix A, using Van Loan's method.
+pub fn discretize_aq<const S: usize>(a: &SMatrix<f64, S, S>, q: &SMatrix<f64, S, S>, dt: f64) -> SMatrix<f64, S, S> {
+  let mut m = DMatrix::<f64>::zeros(2 * S, 2 * S);
+  m.view_mut((0, 0), (S, S)).copy_from(&(-a * dt));
+  m.view_mut((0, S), (S, S)).copy_from(&(q * dt));
+  m.view_mut((S, S), (S, S)).copy_from(&(a.transpose() * dt));
+
+  let phi = m.exp();
+  let phi12 = SMatrix::<f64, S, S>::from_fn(|r, c| phi[(r, S + c)]);
+  let phi22 = SMatrix::<f64, S, S>::from_fn(|r, c| phi[(S + r, S + c)]);
+
+  // Q is symmetric in theory, but may not be due to numerical error
+  let q_d = phi22.transpose() * phi12;
+  (q_d + q_d.transpose()) / 2.0
+}
+
+#[cfg(test)]
+mod test {
+  use approx::assert_relative_eq;
+  use nalgebra::{Matrix1, Matrix2, Vector2, Matrix2x1, Matrix1x2, Vector1};
+
+  use super::{LinearSystem, discretize_aq};
+
+  #[test]
+  fn test_discretize_scalar() {
+    let sys = LinearSystem::new(Matrix1::new(-2.0), Matrix1::new(3.0), Matrix1::new(1.0), Matrix1::new(0.0));
+    let (a, b) = sys.discretize(0.1);
+    let expected_a = (-0.2f64).exp();
+    assert_relative_eq!(a[(0, 0)], expected_a, epsilon = 1e-12);
+    assert_relative_eq!(b[(0, 0)], (expected_a - 1.0) / -2.0 * 3.0, epsilon = 1e-12);
+  }
+
+  #[test]
+  fn test_discretize_q() {
+    let q = discretize_aq(&Matrix1::new(0.0), &Matrix1::new(2.0), 0.5);
+    assert_relative_eq!(q[(0, 0)], 1.0, epsilon = 1e-12);
+  }
+
+  #[test]
+  fn test_discretize_double_integrator() {
+    // x = [position, velocity], u = acceleration. A is singular.
+    let sys = LinearSystem::new(
+      Matrix2::new(0.0, 1.0, 0.0, 0.0), Matrix2x1::new(0.0, 1.0),
+      Matrix1x2::new(1.0, 0.0), Matrix1::new(0.0)
+    );
+    let x = sys.calculate_x(&Vector2::new(1.0, 2.0), &Vector1::new(4.0), 0.5);
+    assert_relative_eq!(x, Vector2::new(1.0 + 2.0 * 0.5 + 0.5 * 4.0 * 0.25, 2.0 + 4.0 * 0.5), epsilon = 1e-12);
+    assert_relative_eq!(sys.calculate_y(&x, &Vector1::new(0.0))[0], x[0]);
+  }
+}
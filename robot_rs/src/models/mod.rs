@@ -1,3 +1,6 @@
 pub mod dc_motor;
+pub mod linear_system;
+pub mod plants;
 
 pub use dc_motor::DcMotor;
+pub use linear_system::LinearSystem;
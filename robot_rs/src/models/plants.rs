@@ -0,0 +1,111 @@
+use nalgebra::{Matrix1, Matrix2, Matrix2x1, Matrix1x2};
+
+use super::{DcMotor, LinearSystem};
+
+// Plants (state-space models) for common mechanisms, derived from their motors. `motor` is the motor model
+// before any reduction (use DcMotor * n for multiple motors on the same gearbox), and `gearing` is the reduction
+// from the motor to the mechanism (greater than 1 is a reduction). All quantities are in SI units (angular velocities in rad/s, even though
+// DcMotor's speeds are in RPM).
+
+/// A flywheel with the given moment of inertia (kg m^2).
+/// States: [angular velocity], Inputs: [voltage], Outputs: [angular velocity]
+pub fn flywheel(motor: &DcMotor, moi: f64, gearing: f64) -> LinearSystem<1, 1, 1> {
+  let (kt, kw, r) = (motor.kt(), motor.kw_rad_per_sec(), motor.R());
+  LinearSystem::new(
+    Matrix1::new(-gearing * gearing * kt / (kw * r * moi)),
+    Matrix1::new(gearing * kt / (r * moi)),
+    Matrix1::new(1.0),
+    Matrix1::new(0.0)
+  )
+}
+
+/// An elevator carrying the given mass (kg) on a drum of the given radius (m).
+/// States: [position, velocity], Inputs: [voltage], Outputs: [position]
+pub fn elevator(motor: &DcMotor, mass: f64, drum_radius: f64, gearing: f64) -> LinearSystem<2, 1, 1> {
+  let (kt, kw, r) = (motor.kt(), motor.kw_rad_per_sec(), motor.R());
+  LinearSystem::new(
+    Matrix2::new(
+      0.0, 1.0,
+      0.0, -gearing * gearing * kt / (r * drum_radius * drum_radius * mass * kw)
+    ),
+    Matrix2x1::new(0.0, gearing * kt / (r * drum_radius * mass)),
+    Matrix1x2::new(1.0, 0.0),
+    Matrix1::new(0.0)
+  )
+}
+
+/// A single-jointed arm with the given moment of inertia about its pivot (kg m^2). Gravity isn't linear, so isn't
+/// included - use ArmFeedforward to cancel it out.
+/// States: [angle, angular velocity], Inputs: [voltage], Outputs: [angle]
+pub fn single_jointed_arm(motor: &DcMotor, moi: f64, gearing: f64) -> LinearSystem<2, 1, 1> {
+  let (kt, kw, r) = (motor.kt(), motor.kw_rad_per_sec(), motor.R());
+  LinearSystem::new(
+    Matrix2::new(
+      0.0, 1.0,
+      0.0, -gearing * gearing * kt / (kw * r * moi)
+    ),
+    Matrix2x1::new(0.0, gearing * kt / (r * moi)),
+    Matrix1x2::new(1.0, 0.0),
+    Matrix1::new(0.0)
+  )
+}
+
+/// A differential (tank) drivetrain, where `motor` is the motor(s) on each side. `trackwidth` is the distance
+/// between the left and right wheels (m), and `moi` is the moment of inertia of the robot about its centre (kg m^2).
+/// States: [left velocity, right velocity], Inputs: [left voltage, right voltage], Outputs: [left velocity, right velocity]
+pub fn differential_drivetrain(motor: &DcMotor, mass: f64, wheel_radius: f64, trackwidth: f64, moi: f64, gearing: f64) -> LinearSystem<2, 2, 2> {
+  let (kt, kw, r) = (motor.kt(), motor.kw_rad_per_sec(), motor.R());
+  let rb = trackwidth / 2.0;
+
+  let c1 = -gearing * gearing * kt / (kw * r * wheel_radius * wheel_radius);
+  let c2 = gearing * kt / (r * wheel_radius);
+  let (same, other) = (1.0 / mass + rb * rb / moi, 1.0 / mass - rb * rb / moi);
+
+  LinearSystem::new(
+    Matrix2::new(same * c1, other * c1, other * c1, same * c1),
+    Matrix2::new(same * c2, other * c2, other * c2, same * c2),
+    Matrix2::identity(),
+    Matrix2::zeros()
+  )
+}
+
+#[cfg(test)]
+mod test {
+  use std::f64::consts::PI;
+
+  use approx::assert_relative_eq;
+  use nalgebra::{Vector1, Vector2};
+
+  use crate::models::DcMotor;
+
+  use super::{flywheel, elevator, differential_drivetrain};
+
+  #[test]
+  fn test_flywheel_free_speed() {
+    // With no load, the flywheel settles at the free speed of the motor (through the gearing). DcMotor::speed is in RPM,
+    // the plant is in rad/s.
+    let motor = DcMotor::neo();
+    let sys = flywheel(&motor, 0.01, 2.0);
+    let x = sys.calculate_x(&Vector1::new(0.0), &Vector1::new(12.0), 100.0);
+    assert_relative_eq!(x[0], motor.speed(0.0, 12.0) * 2.0 * PI / 60.0 / 2.0, max_relative = 1e-6);
+  }
+
+  #[test]
+  fn test_elevator_hold() {
+    // The voltage to hold against gravity gives the same acceleration as gravity, in the other direction
+    let motor = DcMotor::neo();
+    let sys = elevator(&motor, 10.0, 0.05, 20.0);
+    let hold = motor.reduce(20.0).voltage(10.0 * 9.81 * 0.05, 0.0);
+    let accel = (sys.a * Vector2::new(0.0, 0.0) + sys.b * Vector1::new(hold))[1];
+    assert_relative_eq!(accel, 9.81, max_relative = 1e-9);
+  }
+
+  #[test]
+  fn test_drivetrain_symmetry() {
+    let sys = differential_drivetrain(&(DcMotor::neo() * 2.0), 50.0, 0.0762, 0.6, 5.0, 8.45);
+    let x = sys.calculate_x(&Vector2::zeros(), &Vector2::new(6.0, 6.0), 0.5);
+    assert_relative_eq!(x[0], x[1]);
+    let x = sys.calculate_x(&Vector2::zeros(), &Vector2::new(-6.0, 6.0), 0.5);
+    assert_relative_eq!(x[0], -x[1]);
+  }
+}
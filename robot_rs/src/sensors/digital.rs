@@ -1,4 +1,4 @@
-use wpilib_hal::{HAL_InitializeDIOPort, HAL_GetPort, HAL_DigitalHandle, HAL_SetDIODirection, HAL_GetDIO, HAL_SetDIO, HAL_FreeDIOPort, hal_safe_call};
+use wpilib_hal::{HAL_InitializeDIOPort, HAL_GetPort, HAL_DigitalHandle, HAL_SetDIODirection, HAL_GetDIO, HAL_SetDIO, HAL_FreeDIOPort, HAL_Pulse, HAL_IsPulsing, HAL_DigitalPWMHandle, HAL_AllocateDigitalPWM, HAL_FreeDigitalPWM, HAL_SetDigitalPWMRate, HAL_SetDigitalPWMDutyCycle, HAL_SetDigitalPWMOutputChannel, HAL_GetNumDigitalChannels, hal_safe_call};
 
 use crate::macros::{wrapped_traits, wrapped_traits_nogen};
 
@@ -95,12 +95,95 @@ impl DigitalInput for DigitalRoboRIOOutput {
   }
 }
 
+impl DigitalRoboRIOOutput {
+  /// Output a single pulse on the port, of the given length (in seconds)
+  pub fn pulse(&mut self, length: f64) {
+    hal_safe_call!(HAL_Pulse(self.handle, length)).unwrap()
+  }
+
+  pub fn is_pulsing(&self) -> bool {
+    hal_safe_call!(HAL_IsPulsing(self.handle)).unwrap() != 0
+  }
+
+  /// Drive this output from one of the digital PWM generators, with the given duty cycle (0..1)
+  pub fn pwm(self, duty_cycle: f64) -> DigitalRoboRIOPWM {
+    DigitalRoboRIOPWM::new(self, duty_cycle)
+  }
+
+  /// Toggle the output with the given period (in seconds), forever. This is an async function, so
+  /// can be spawned as its own task, or raced against something else with tokio::select!.
+  pub async fn blink(&mut self, period: f64) {
+    if !period.is_finite() || period <= 0.0 {
+      return
+    }
+
+    loop {
+      let value = self.get();
+      self.set(!value);
+      tokio::time::sleep(tokio::time::Duration::from_secs_f64(period / 2.0)).await;
+    }
+  }
+}
+
 impl DigitalOutput for DigitalRoboRIOOutput {
   fn set(&mut self, value: bool) {
     hal_safe_call!(HAL_SetDIO(self.handle, value as i32)).unwrap()
   }
 }
 
+struct DigitalPWMGenerator(HAL_DigitalPWMHandle);
+
+impl Drop for DigitalPWMGenerator {
+  fn drop(&mut self) {
+    // Disconnect the generator from the port before freeing it
+    let _ = hal_safe_call!(HAL_SetDigitalPWMOutputChannel(self.0, HAL_GetNumDigitalChannels()));
+    let _ = hal_safe_call!(HAL_FreeDigitalPWM(self.0));
+  }
+}
+
+/// A RoboRIO digital output driven by a PWM generator. There are a limited number of generators (6),
+/// which all share the same rate.
+// The generator is declared first so that it's dropped (disconnected from the port) before the port is freed.
+pub struct DigitalRoboRIOPWM(DigitalPWMGenerator, DigitalRoboRIOOutput);
+
+impl std::ops::Deref for DigitalRoboRIOPWM {
+  type Target = DigitalRoboRIOOutput;
+
+  fn deref(&self) -> &Self::Target {
+    &self.1
+  }
+}
+
+impl std::ops::DerefMut for DigitalRoboRIOPWM {
+  fn deref_mut(&mut self) -> &mut Self::Target {
+    &mut self.1
+  }
+}
+
+impl DigitalRoboRIOPWM {
+  pub fn new(output: DigitalRoboRIOOutput, duty_cycle: f64) -> Self {
+    let generator = DigitalPWMGenerator(hal_safe_call!(HAL_AllocateDigitalPWM()).unwrap());
+    hal_safe_call!(HAL_SetDigitalPWMDutyCycle(generator.0, duty_cycle.clamp(0.0, 1.0))).unwrap();
+    hal_safe_call!(HAL_SetDigitalPWMOutputChannel(generator.0, output.port() as i32)).unwrap();
+
+    Self(generator, output)
+  }
+
+  /// Stop driving the output from the PWM generator, freeing the generator
+  pub fn revert(self) -> DigitalRoboRIOOutput {
+    self.1
+  }
+
+  pub fn set_duty_cycle(&mut self, duty_cycle: f64) {
+    hal_safe_call!(HAL_SetDigitalPWMDutyCycle(self.0.0, duty_cycle.clamp(0.0, 1.0))).unwrap()
+  }
+
+  /// Set the rate (in Hz) of all digital PWM generators. The rate is shared between all generators.
+  pub fn set_rate(rate: f64) {
+    hal_safe_call!(HAL_SetDigitalPWMRate(rate)).unwrap()
+  }
+}
+
 #[cfg(test)]
 mod test {
   use super::{DigitalRoboRIO, DigitalOutput, DigitalInput, InvertOutput};
@@ -122,4 +205,33 @@ mod test {
     assert_eq!(out.0.get(), true);
     assert_eq!(out.revert().get(), true);
   }
+
+  #[test]
+  fn test_pulse() {
+    let mut out = DigitalRoboRIO::new(2).output();
+    out.pulse(0.01);
+    assert!(out.is_pulsing());
+  }
+
+  #[test]
+  fn test_pwm() {
+    let mut pwm = DigitalRoboRIO::new(3).output().pwm(0.5);
+    pwm.set_duty_cycle(0.25);
+    assert_eq!(pwm.port(), 3);
+
+    // Reverting frees the generator, so it can be reallocated
+    let out = pwm.revert();
+    let pwm = out.pwm(0.75);
+    assert_eq!(pwm.port(), 3);
+  }
+
+  #[test]
+  fn test_blink_invalid_period() {
+    let mut out = DigitalRoboRIO::new(4).output();
+    out.set(false);
+    // These would otherwise loop forever
+    futures::executor::block_on(out.blink(-1.0));
+    futures::executor::block_on(out.blink(f64::NAN));
+    assert!(!out.get());
+  }
 }
\ No newline at end of file
@@ -1,3 +1,4 @@
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct MinMax<T> {
   pub min: T,
   pub max: T
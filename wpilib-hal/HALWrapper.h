@@ -1,4 +1,6 @@
 #include <hal/HAL.h>
 #include <hal/CANAPI.h>
 #include <hal/Encoder.h>
-#include <ntcore.h>
\ No newline at end of file
+#include <hal/simulation/DriverStationData.h>
+#include <ntcore.h>
+#include <wpi/Synchronization.h>
\ No newline at end of file
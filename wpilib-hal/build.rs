@@ -3,7 +3,7 @@ extern crate bindgen;
 use std::env;
 use std::path::PathBuf;
 
-const SYMBOL_REGEX: &str = r"(HAL_|NT_)\w+";
+const SYMBOL_REGEX: &str = r"(HAL_|HALSIM_|NT_|WPI_)\w+";
 
 fn main() {
   println!("cargo:rustc-link-search={}", PathBuf::from("libs").canonicalize().unwrap().to_str().unwrap());